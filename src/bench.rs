@@ -0,0 +1,122 @@
+use std::cmp;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chess::*;
+
+use cli::SearchOptions;
+
+/**************************************************************************************************/
+/*******************************     BENCH DEFINITION     *****************************************/
+/**************************************************************************************************/
+// 默认深度（包含根节点的一步）
+pub const BENCH_DEFAULT_DEPTH: usize = 4;
+
+// 固定测试局面，修改后节点数签名会发生变化
+const BENCH_POSITIONS: [&str; 6] = [
+    "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w",
+    "rnbakab1r/9/1c4nc1/p1p1p1p1p/9/9/P1P1P1P1P/1C2C4/9/RNBAKABNR w",
+    "r1bakab1r/9/1cn3nc1/p1p1p1p1p/9/9/P1P1P1P1P/1CN3NC1/9/R1BAKAB1R b",
+    "2bakab2/9/2n1c1n2/p1p1p3p/6p2/2P6/P3P1P1P/2N1C1N2/4A4/2BAK1B2 w",
+    "3k5/4a4/4b4/9/2p6/6R2/9/9/4A4/3AK4 w",
+    "4k4/4a4/4b4/9/9/2N6/9/4C4/9/3K5 b",
+];
+
+/// 性能测试
+///
+/// 以固定深度检索所有测试局面，打印每个局面和合计的节点数、时间、NPS，返回节点数合计（签名）和时间合计。
+/// 检索行为不变时节点数合计不变，可用于确认重构没有改变检索结果（只限单线程，多线程时节点数每次不同）。
+///
+/// * `depth` - 深度（包含根节点的一步）。
+/// * `search` - 检索设置（线程数、选择性检索）。
+pub fn run(depth: usize, search: SearchOptions) -> (u64, Duration) {
+    let mut total_nodes = 0u64;
+    let mut total_time = Duration::from_millis(0);
+
+    for (i, fen) in BENCH_POSITIONS.iter().enumerate() {
+        let mut position = Position::from_fen(fen).unwrap();
+        search.apply(&mut position);
+        let start: Instant = Instant::now();
+        let result: Option<SearchResult> = position.search(SearchLimit::depth(depth));
+        let time: Duration = start.elapsed();
+        let (best_move, nodes) = match result {
+            Some(r) => (r.best_move.to_iccs(), r.nodes),
+            None => (String::from("none"), 0u64),
+        };
+
+        total_nodes += nodes;
+        total_time += time;
+        println!(
+            "position---number=[{}],bestmove=[{}],nodes=[{}],time=[{}ms]",
+            i + 1,
+            best_move,
+            nodes,
+            time.as_millis()
+        );
+    }
+
+    println!(
+        "bench---depth=[{}],threads=[{}],nodes=[{}],time=[{}ms],nps=[{}]",
+        depth,
+        search.threads,
+        total_nodes,
+        total_time.as_millis(),
+        nodes_per_second(total_nodes, total_time)
+    );
+    println!("signature---{}", total_nodes);
+    (total_nodes, total_time)
+}
+
+/// 并行检索的加速比测试
+///
+/// 以单线程和指定的线程数分别执行性能测试，打印到达相同深度的时间之比。
+/// 线程数超过可用的CPU数时加速比没有意义，打印警告（`warning`）。
+///
+/// * `depth` - 深度（包含根节点的一步）。
+/// * `search` - 检索设置（线程数、选择性检索）。
+pub fn speedup(depth: usize, search: SearchOptions) -> f64 {
+    let threads: usize = search.threads;
+    let parallelism: usize = thread::available_parallelism().map(|n| n.get()).unwrap_or(1usize);
+    if threads > parallelism {
+        println!(
+            "warning---threads=[{}],available_parallelism=[{}],message=[more threads than CPUs, speedup is not meaningful]",
+            threads, parallelism
+        );
+    }
+    let (_, single) = run(depth, SearchOptions { threads: 1usize, ..search.clone() });
+    let (_, parallel) = run(depth, search);
+    let speedup: f64 = single.as_secs_f64() / parallel.as_secs_f64().max(1e-6f64);
+    println!(
+        "speedup---depth=[{}],threads=[{}],time_1=[{}ms],time_{}=[{}ms],speedup=[{:.2}]",
+        depth,
+        threads,
+        single.as_millis(),
+        threads,
+        parallel.as_millis(),
+        speedup
+    );
+    speedup
+}
+
+/// 每秒节点数计算
+///
+/// * `nodes` - 节点数。
+/// * `time` - 经过时间。
+pub fn nodes_per_second(nodes: u64, time: Duration) -> u64 {
+    let micros: u128 = cmp::max(time.as_micros(), 1u128);
+    (nodes as u128 * 1_000_000u128 / micros) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 深度3的节点数签名（检索或者评价改变时更新）
+    const SIGNATURE_DEPTH_3: u64 = 49365;
+
+    #[test]
+    fn signature_is_stable_at_fixed_depth() {
+        let (nodes, _) = run(3usize, SearchOptions::default());
+        assert_eq!(nodes, SIGNATURE_DEPTH_3);
+    }
+}
@@ -0,0 +1,923 @@
+use std::cmp;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use chess::*;
+
+use bench;
+use epd;
+use play;
+use server;
+use tune;
+use ucci;
+
+/**************************************************************************************************/
+/*******************************      CLI DEFINITION      *****************************************/
+/**************************************************************************************************/
+const DEFAULT_CONFIG: &str = "resources/server_init.yaml";
+const DEFAULT_EPD: &str = "resources/tactics.epd";
+const DEFAULT_DEPTH: usize = 4;
+const DEFAULT_PERFT_DEPTH: usize = 3;
+const DEFAULT_SELFPLAY_MOVES: usize = 200;
+const DEFAULT_MATCH_GAMES: usize = 20;
+// 级别阶梯的每一组对局数和种子（`SKILL_LEVELS`的等级分的测定条件）
+const DEFAULT_LADDER_GAMES: usize = 40;
+const DEFAULT_LADDER_SEED: u64 = 1;
+const DEFAULT_TUNE_OUTPUT: &str = "resources/evaluate_tuned.yaml";
+const DEFAULT_TUNE_ITERATIONS: usize = 100;
+
+const USAGE: &str = "usage: chess <command> [options]
+
+commands:
+  serve     [--config <file>]                       start the socket server (default)
+  bestmove  [--fen <fen>] [--depth <n>] [--nodes <n>] [--time <ms>] [engine options]
+                                                     print the best move
+  perft     [--fen <fen>] [--depth <n>]              count legal move paths
+  bench     [--depth <n>]                            search fixed positions, print nodes and nps
+  analyze   [--fen <fen>] [--depth <n>] [--multipv <n>]
+                                                     print each iteration of the search (best <n> root moves)
+  eval      [--fen <fen>]                            print the evaluation breakdown per side and piece
+  selfplay  [--fen <fen>] [--depth <n>] [--nodes <n>] [--time <ms>] [--moves <n>]
+            [--rule asian|chinese] [engine options]
+                                                     let the engine play against itself
+  epd       [--file <file>] [--depth <n>] [--nodes <n>] [--time <ms>]
+                                                     run an EPD test suite
+  play      [--side red|black] [--fen <fen>] [--depth <n>] [--nodes <n>] [--time <ms>]
+            [--glyphs chinese|latin] [--rule asian|chinese] [engine options]
+                                                     play against the engine in the terminal
+  tune      --file <file> [--output <file>] [--iterations <n>]
+                                                     tune the evaluation from labelled quiet positions
+  match     [--fen <fen>] [--depth <n>] [--nodes <n>] [--time <ms>] [--moves <n>]
+            [--rule asian|chinese] --skill <level> --opponent <level> [--games <n>] [--seed <n>]
+                                                     play skill levels against each other, print the Elo difference
+  ladder    [--fen <fen>] [--depth <n>] [--nodes <n>] [--time <ms>] [--moves <n>]
+            [--rule asian|chinese] [--games <n>] [--seed <n>]
+                                                     match every pair of adjacent skill levels, print the Elo of each level
+  ucci                                               speak the UCCI protocol on stdin/stdout
+  help                                               print this message
+
+options for every command:
+  --eval <file>     load evaluation parameters (YAML, or JSON with .json extension)
+  --threads <n>     search with <n> threads (Lazy SMP, default 1); bench also compares against 1 thread
+  --hash <mb>       transposition table size in MB (default 16, rounded down to a power of two)
+  --pruning <list>  selective search: all (default), none, or a comma list of null,lmr,futility,ext,pvs,asp
+
+engine options (bestmove, selfplay, play):
+  --random <margin> pick randomly among root moves scoring within <margin> of the best one
+  --skill <level>   limit the playing strength, 0 (weakest) to 10 (full strength)
+  --seed <n>        make --random and --skill reproducible (default: taken from the clock)";
+
+/// 子命令
+#[derive(Debug)]
+pub enum Command {
+    Serve { config: String },
+    BestMove { fen: String, limit: SearchLimit, engine: EngineOptions },
+    Perft { fen: String, depth: usize },
+    Bench { depth: usize },
+    Analyze { fen: String, depth: usize, multipv: usize },
+    Eval { fen: String },
+    SelfPlay {
+        fen: String,
+        limit: SearchLimit,
+        moves: usize,
+        rule: RepetitionRule,
+        engine: EngineOptions,
+    },
+    Epd { file: String, limit: SearchLimit },
+    Play {
+        fen: String,
+        side: Side,
+        limit: SearchLimit,
+        glyphs: play::Glyphs,
+        rule: RepetitionRule,
+        engine: EngineOptions,
+    },
+    Tune { file: String, output: String, iterations: usize },
+    Match {
+        fen: String,
+        limit: SearchLimit,
+        moves: usize,
+        rule: RepetitionRule,
+        skills: [usize; 2],
+        games: usize,
+        seed: u64,
+    },
+    Ladder {
+        fen: String,
+        limit: SearchLimit,
+        moves: usize,
+        rule: RepetitionRule,
+        games: usize,
+        seed: u64,
+    },
+    Ucci,
+    Help,
+}
+
+/// 命令行参数
+#[derive(Debug)]
+pub struct Arguments {
+    pub command: Command,
+    /// 评价参数文件（`--eval`），服务器时优先于配置文件。
+    pub evaluate_file: Option<String>,
+    /// 检索线程数（`--threads`），服务器时优先于配置文件。
+    pub threads: Option<usize>,
+    /// 置换表大小（`--hash`，MB），服务器时优先于配置文件。
+    pub hash: Option<usize>,
+    /// 选择性检索（`--pruning`），服务器时优先于配置文件。
+    pub selectivity: Option<Selectivity>,
+}
+
+/// 命令行解析
+///
+/// 解析`chess <command> [--option value]...`，没有子命令时启动服务器。
+///
+/// * `args` - 命令行参数（不包含程序名）。
+pub fn parse(args: &[String]) -> Result<Arguments, String> {
+    let name: &str = match args.first() {
+        Some(n) => n.as_str(),
+        None => {
+            return Ok(Arguments {
+                command: Command::Serve { config: String::from(DEFAULT_CONFIG) },
+                evaluate_file: None,
+                threads: None,
+                hash: None,
+                selectivity: None,
+            })
+        }
+    };
+    let options: Options = Options::parse(&args[1..])?;
+    let allowed: &[&str] = match name {
+        "serve" => &["config"],
+        "bestmove" => &["fen", "depth", "time", "nodes", "random", "skill", "seed"],
+        "perft" => &["fen", "depth"],
+        "analyze" => &["fen", "depth", "multipv"],
+        "bench" => &["depth"],
+        "eval" => &["fen"],
+        "selfplay" => &["fen", "depth", "time", "nodes", "moves", "rule", "random", "skill", "seed"],
+        "epd" => &["file", "depth", "time", "nodes"],
+        "play" => &["fen", "side", "depth", "time", "nodes", "glyphs", "rule", "random", "skill", "seed"],
+        "match" => &["fen", "depth", "time", "nodes", "moves", "rule", "skill", "opponent", "games", "seed"],
+        "ladder" => &["fen", "depth", "time", "nodes", "moves", "rule", "games", "seed"],
+        "tune" => &["file", "output", "iterations"],
+        "ucci" | "help" | "--help" | "-h" => &[],
+        _ => return Err(format!("unknown command [{}]", name)),
+    };
+    options.check(allowed, &["eval", "threads", "hash", "pruning"])?;
+
+    let command = match name {
+        "serve" => Command::Serve { config: options.string("config", DEFAULT_CONFIG) },
+        "bestmove" => Command::BestMove {
+            fen: options.string("fen", START_FEN),
+            limit: options.limit()?,
+            engine: options.engine()?,
+        },
+        "perft" => Command::Perft {
+            fen: options.string("fen", START_FEN),
+            depth: options.number("depth", DEFAULT_PERFT_DEPTH as u64)? as usize,
+        },
+        "bench" => Command::Bench {
+            depth: options.number("depth", bench::BENCH_DEFAULT_DEPTH as u64)? as usize,
+        },
+        "analyze" => Command::Analyze {
+            fen: options.string("fen", START_FEN),
+            depth: options.number("depth", DEFAULT_DEPTH as u64)? as usize,
+            multipv: match options.number("multipv", 1u64)? as usize {
+                0usize => return Err(String::from("[--multipv] must be at least 1")),
+                n => n,
+            },
+        },
+        "eval" => Command::Eval { fen: options.string("fen", START_FEN) },
+        "selfplay" => Command::SelfPlay {
+            fen: options.string("fen", START_FEN),
+            limit: options.limit()?,
+            moves: options.number("moves", DEFAULT_SELFPLAY_MOVES as u64)? as usize,
+            rule: options.rule()?,
+            engine: options.engine()?,
+        },
+        "epd" => Command::Epd {
+            file: options.string("file", DEFAULT_EPD),
+            limit: options.limit()?,
+        },
+        "play" => Command::Play {
+            fen: options.string("fen", START_FEN),
+            side: match options.string("side", "red").as_str() {
+                "red" => Side::Red,
+                "black" => Side::Black,
+                side => return Err(format!("invalid side [{}]", side)),
+            },
+            limit: options.limit()?,
+            glyphs: match options.string("glyphs", "chinese").as_str() {
+                "chinese" => play::Glyphs::Chinese,
+                "latin" => play::Glyphs::Latin,
+                glyphs => return Err(format!("invalid glyphs [{}]", glyphs)),
+            },
+            rule: options.rule()?,
+            engine: options.engine()?,
+        },
+        "match" => Command::Match {
+            fen: options.string("fen", START_FEN),
+            limit: options.limit()?,
+            moves: options.number("moves", DEFAULT_SELFPLAY_MOVES as u64)? as usize,
+            rule: options.rule()?,
+            skills: [options.level("skill")?, options.level("opponent")?],
+            games: options.number("games", DEFAULT_MATCH_GAMES as u64)? as usize,
+            seed: options.seed()?,
+        },
+        "ladder" => Command::Ladder {
+            fen: options.string("fen", START_FEN),
+            limit: options.limit()?,
+            moves: options.number("moves", DEFAULT_SELFPLAY_MOVES as u64)? as usize,
+            rule: options.rule()?,
+            games: options.number("games", DEFAULT_LADDER_GAMES as u64)? as usize,
+            seed: options.number("seed", DEFAULT_LADDER_SEED)?,
+        },
+        "tune" => Command::Tune {
+            file: options.get("file").cloned().ok_or("missing option [--file]")?,
+            output: options.string("output", DEFAULT_TUNE_OUTPUT),
+            iterations: options.number("iterations", DEFAULT_TUNE_ITERATIONS as u64)? as usize,
+        },
+        "ucci" => Command::Ucci,
+        _ => Command::Help,
+    };
+    Ok(Arguments {
+        command,
+        evaluate_file: options.get("eval").cloned(),
+        threads: match options.get("threads") {
+            Some(_) => Some(options.number("threads", 1u64)? as usize),
+            None => None,
+        },
+        hash: match options.get("hash") {
+            Some(_) => Some(options.number("hash", DEFAULT_HASH_SIZE as u64)? as usize),
+            None => None,
+        },
+        selectivity: match options.get("pruning") {
+            Some(text) => Some(Selectivity::parse(text)?),
+            None => None,
+        },
+    })
+}
+
+/// 命令执行
+///
+/// 指定了评价参数文件时先读取，读取失败时返回Err；评价参数、线程数等检索设置由各子命令设置到新建的局面。
+///
+/// * `arguments` - 命令行参数。
+pub fn execute(arguments: Arguments) -> Result<(), String> {
+    let mut search = SearchOptions::default();
+    if let Some(file) = &arguments.evaluate_file {
+        search.params = Arc::new(EvaluateParams::load(file)?);
+    }
+    if let Some(threads) = arguments.threads {
+        search.threads = threads;
+    }
+    if let Some(hash) = arguments.hash {
+        search.hash = hash;
+    }
+    if let Some(selectivity) = arguments.selectivity {
+        search.selectivity = selectivity;
+    }
+    match arguments.command {
+        Command::Serve { config } => {
+            let mut server = server::Server::new(&config);
+            if arguments.evaluate_file.is_none() {
+                if let Some(file) = &server.config().evaluate_file {
+                    search.params = Arc::new(EvaluateParams::load(file)?);
+                }
+            }
+            if let (None, Some(threads)) = (arguments.threads, server.config().threads) {
+                search.threads = threads;
+            }
+            if let (None, Some(hash)) = (arguments.hash, server.config().hash) {
+                search.hash = hash;
+            }
+            if let (None, Some(pruning)) = (arguments.selectivity, &server.config().pruning) {
+                search.selectivity = Selectivity::parse(pruning)?;
+            }
+            server.startup(search);
+        }
+        Command::BestMove { fen, limit, engine } => best_move(&fen, limit, engine, search)?,
+        Command::Perft { fen, depth } => perft(&fen, depth)?,
+        Command::Bench { depth } => {
+            if search.threads > 1usize {
+                bench::speedup(depth, search);
+            } else {
+                bench::run(depth, search);
+            }
+        }
+        Command::Analyze { fen, depth, multipv } => analyze(&fen, depth, multipv, search)?,
+        Command::Eval { fen } => evaluate(&fen, search)?,
+        Command::SelfPlay { fen, limit, moves, rule, engine } => {
+            self_play(&fen, limit, moves, rule, engine, search)?
+        }
+        Command::Epd { file, limit } => {
+            epd::run(&file, limit, search);
+        }
+        Command::Play { fen, side, limit, glyphs, rule, engine } => {
+            play::run(&fen, side, limit, glyphs, rule, engine, search)?
+        }
+        Command::Match { fen, limit, moves, rule, skills, games, seed } => {
+            play_match(&fen, limit, moves, rule, skills, games, seed, &search)?;
+        }
+        Command::Ladder { fen, limit, moves, rule, games, seed } => {
+            ladder(&fen, limit, moves, rule, games, seed, &search)?
+        }
+        Command::Tune { file, output, iterations } => {
+            let mut positions: Vec<tune::TunePosition> = tune::load(&file)?;
+            tune::run(&mut positions, (*search.params).clone(), iterations, &output)?;
+        }
+        Command::Ucci => ucci::run(search),
+        Command::Help => println!("{}", USAGE),
+    }
+    Ok(())
+}
+
+/// 使用方法取得
+pub fn usage() -> &'static str {
+    USAGE
+}
+
+/// 最佳着法
+///
+/// 检索指定局面并打印最佳着法。
+///
+/// * `fen` - FEN字符串。
+/// * `limit` - 检索限制。
+/// * `engine` - 着法随机化和棋力级别。
+/// * `search` - 检索设置。
+fn best_move(fen: &str, limit: SearchLimit, engine: EngineOptions, search: SearchOptions) -> Result<(), String> {
+    let mut position = Position::from_fen(fen)?;
+    search.apply(&mut position);
+    engine.apply(&mut position);
+    match position.search(limit) {
+        Some(result) => println!("bestmove {}", result.best_move.to_iccs()),
+        None => println!("nobestmove"),
+    }
+    Ok(())
+}
+
+/// 走法数统计
+///
+/// 打印每个根着法的叶子节点数和合计。
+///
+/// * `fen` - FEN字符串。
+/// * `depth` - 深度。
+fn perft(fen: &str, depth: usize) -> Result<(), String> {
+    let mut position = Position::from_fen(fen)?;
+    let start: Instant = Instant::now();
+    let mut total = 0u64;
+    if depth > 0usize {
+        for m in position.legal_moves() {
+            position.make_move(m)?;
+            let nodes: u64 = position.perft(depth - 1usize);
+            position.undo_move();
+            println!("{}: {}", m.to_iccs(), nodes);
+            total += nodes;
+        }
+    } else {
+        total = 1u64;
+    }
+    println!(
+        "perft---depth=[{}],nodes=[{}],time=[{}ms]",
+        depth,
+        total,
+        start.elapsed().as_millis()
+    );
+    Ok(())
+}
+
+/// 局面分析
+///
+/// 从深度1开始迭代加深，打印每一层的最佳着法、评价值、节点数和时间，以及渴望窗口的失败（`aspiration`）。
+/// `multipv`大于1时每一层打印评价值最高的`multipv`个着法（`multipv=[序号]`，不使用渴望窗口）。
+///
+/// * `fen` - FEN字符串。
+/// * `depth` - 最大深度。
+/// * `multipv` - 变例数。
+/// * `search` - 检索设置。
+fn analyze(fen: &str, depth: usize, multipv: usize, search: SearchOptions) -> Result<(), String> {
+    let mut position = Position::from_fen(fen)?;
+    search.apply(&mut position);
+    let start: Instant = Instant::now();
+    for d in 1usize..=depth {
+        let results: Vec<SearchResult> = position.search_multipv(SearchLimit::depth(d), multipv);
+        let elapsed: Duration = start.elapsed();
+        if results.is_empty() {
+            println!("info---depth=[{}],bestmove=[none]", d);
+            break;
+        }
+        // 该层渴望窗口的失败（fail high/fail low）
+        for fail in position.aspiration_fails().iter().filter(|fail| fail.depth == d) {
+            println!(
+                "aspiration---depth=[{}],fail=[{}],score=[{}],window=[{},{}]",
+                fail.depth,
+                if fail.high { "high" } else { "low" },
+                fail.score,
+                fail.window.0,
+                fail.window.1
+            );
+        }
+        for (i, r) in results.iter().enumerate() {
+            let head: String = if multipv > 1usize {
+                format!("depth=[{}],multipv=[{}],move=[{}]", d, i + 1usize, r.best_move.to_iccs())
+            } else {
+                format!("depth=[{}],bestmove=[{}]", d, r.best_move.to_iccs())
+            };
+            println!(
+                "info---{},score=[{}],nodes=[{}],time=[{}ms],nps=[{}],pv=[{}],pv_chinese=[{}]",
+                head,
+                r.score_text(),
+                r.nodes,
+                elapsed.as_millis(),
+                bench::nodes_per_second(r.nodes, elapsed),
+                r.pv_iccs(),
+                position.line_to_chinese(&r.pv).join(" ")
+            );
+        }
+    }
+    Ok(())
+}
+
+/// 评价明细
+///
+/// 打印双方每个棋子的评价项目、帅（将）的安全性、双方的合计以及阶段值和评价值。
+/// 评价项目为该方视角，评价值为黑方视角（黑方减红方）。
+///
+/// * `fen` - FEN字符串。
+/// * `search` - 检索设置（评价参数）。
+fn evaluate(fen: &str, search: SearchOptions) -> Result<(), String> {
+    let mut position = Position::from_fen(fen)?;
+    search.apply(&mut position);
+    let trace: EvaluateTrace = position.evaluate_trace();
+    for side in &[&trace.red, &trace.black] {
+        for piece in &side.pieces {
+            println!(
+                "piece---side=[{:?}],piece=[{}],square=[{}],material=[{}/{}],position=[{}/{}],mobility=[{}],cannon_platform=[{}],rook_file=[{}]",
+                side.side,
+                piece.piece,
+                piece.to_iccs(),
+                piece.material[0],
+                piece.material[1],
+                piece.position[0],
+                piece.position[1],
+                piece.mobility,
+                piece.cannon_platform,
+                piece.rook_file
+            );
+        }
+        let terms: Vec<String> = KING_SAFETY_TERMS
+            .iter()
+            .zip(side.king_safety.iter())
+            .map(|(name, value)| format!("{}=[{}]", name, value))
+            .collect();
+        println!(
+            "king_safety---side=[{:?}],{},total=[{}]",
+            side.side,
+            terms.join(","),
+            side.king_safety.iter().sum::<i32>()
+        );
+        println!(
+            "side---side=[{:?}],middlegame=[{}],endgame=[{}],activity=[{}]",
+            side.side,
+            side.middlegame(),
+            side.endgame(),
+            side.activity()
+        );
+    }
+    println!("phase---phase=[{}],total=[{}]", trace.phase, trace.total_phase);
+    println!("score---black-red=[{}],side=[{:?}]", trace.score, position.side());
+    Ok(())
+}
+
+/// 自我对局
+///
+/// 引擎执红黑双方对局，无合法着法的一方判负，达到最大步数时结束。
+///
+/// * `fen` - 开始局面。
+/// * `limit` - 每一步的检索限制。
+/// * `moves` - 最大步数（半回合）。
+/// * `rule` - 重复局面规则。
+/// * `engine` - 着法随机化和棋力级别，指定时打印种子以便重现对局。
+/// * `search` - 检索设置。
+fn self_play(
+    fen: &str,
+    limit: SearchLimit,
+    moves: usize,
+    rule: RepetitionRule,
+    engine: EngineOptions,
+    search: SearchOptions,
+) -> Result<(), String> {
+    let mut position = Position::from_fen(fen)?;
+    position.set_rule(rule);
+    search.apply(&mut position);
+    if let Some(randomness) = engine.randomness {
+        println!("random---margin=[{}],seed=[{}]", randomness.margin, randomness.seed);
+    }
+    if let Some(skill) = engine.skill {
+        println!("skill---level=[{}],elo=[{}],seed=[{}]", skill.level, skill.elo(), skill.seed);
+    }
+    engine.apply(&mut position);
+    for number in 1usize..=moves {
+        let side: Side = position.side();
+        let (winner, reason) = match position.status() {
+            GameStatus::Ongoing => (None, None),
+            GameStatus::Win(winner, reason) => (Some(winner), Some(reason)),
+            GameStatus::Draw(reason) => (None, Some(reason)),
+        };
+        if let Some(reason) = reason {
+            println!(
+                "result---winner=[{:?}],reason=[{:?}],moves=[{}]",
+                winner,
+                reason,
+                number - 1usize
+            );
+            println!("fen---{}", position.to_fen());
+            return Ok(());
+        }
+        let result: SearchResult = position.search(limit).unwrap();
+        println!(
+            "move---number=[{}],side=[{:?}],move=[{}],score=[{}],pv=[{}]",
+            number,
+            side,
+            result.best_move.to_iccs(),
+            result.score_text(),
+            result.pv_iccs()
+        );
+        position.make_move(result.best_move)?;
+    }
+    println!("result---winner=[None],reason=[None],moves=[{}]", moves);
+    println!("fen---{}", position.to_fen());
+    Ok(())
+}
+
+/// 引擎选项
+///
+/// 着法随机化和棋力级别（都没有指定时为最强、不随机）。
+#[derive(Debug, Clone, Copy)]
+pub struct EngineOptions {
+    pub randomness: Option<Randomness>,
+    pub skill: Option<Skill>,
+}
+
+impl EngineOptions {
+    /// 局面设置
+    ///
+    /// 棋力级别会覆盖着法随机化的设置，所以先设置棋力级别。
+    ///
+    /// * `position` - 局面。
+    pub fn apply(&self, position: &mut Position) {
+        if let Some(skill) = self.skill {
+            position.set_skill(skill);
+        }
+        if let Some(randomness) = self.randomness {
+            position.set_randomness(randomness);
+        }
+    }
+}
+
+/// 检索设置
+///
+/// 所有子命令共通的`--eval`、`--threads`、`--hash`和`--pruning`，新建的局面各自设置。
+#[derive(Debug, Clone)]
+pub struct SearchOptions {
+    /// 评价参数（默认为内置的默认值）。
+    pub params: Arc<EvaluateParams>,
+    /// 检索线程数（默认为1）。
+    pub threads: usize,
+    /// 置换表大小（MB，默认为`DEFAULT_HASH_SIZE`）。
+    pub hash: usize,
+    /// 选择性检索（默认全部有效）。
+    pub selectivity: Selectivity,
+}
+
+impl Default for SearchOptions {
+    fn default() -> SearchOptions {
+        SearchOptions {
+            params: Arc::new(EvaluateParams::default()),
+            threads: 1usize,
+            hash: DEFAULT_HASH_SIZE,
+            selectivity: Selectivity::all(),
+        }
+    }
+}
+
+impl SearchOptions {
+    /// 局面设置
+    ///
+    /// * `position` - 局面。
+    pub fn apply(&self, position: &mut Position) {
+        position.set_evaluate_params(self.params.clone());
+        position.set_threads(self.threads);
+        position.set_hash_size(self.hash);
+        position.set_selectivity(self.selectivity);
+    }
+}
+
+/// 级别对局
+///
+/// 两个棋力级别交换先后手对局，打印每一局的结果、合计得分以及由得分率推算的等级分差，返回第一个级别的得分率。
+/// 每一局、每一步使用不同的种子，超过最大步数时判和。
+///
+/// * `fen` - 开始局面。
+/// * `limit` - 每一步的检索限制。
+/// * `moves` - 每一局的最大步数（半回合）。
+/// * `rule` - 重复局面规则。
+/// * `skills` - 两个棋力级别（第一个为测定对象）。
+/// * `games` - 对局数。
+/// * `seed` - 种子。
+/// * `search` - 检索设置。
+#[allow(clippy::too_many_arguments)]
+fn play_match(
+    fen: &str,
+    limit: SearchLimit,
+    moves: usize,
+    rule: RepetitionRule,
+    skills: [usize; 2],
+    games: usize,
+    seed: u64,
+    search: &SearchOptions,
+) -> Result<f64, String> {
+    let start: Instant = Instant::now();
+    // 第一个级别的胜、和、负
+    let mut results = [0usize; 3];
+    for game in 0usize..games {
+        // 偶数局第一个级别执红
+        let red: usize = game % 2usize;
+        let mut position = Position::from_fen(fen)?;
+        position.set_rule(rule);
+        search.apply(&mut position);
+        let mut winner: Option<Side> = None;
+        let mut reason: Option<Reason> = None;
+        let mut plies = 0usize;
+        while plies < moves {
+            match position.status() {
+                GameStatus::Ongoing => {}
+                GameStatus::Win(w, r) => {
+                    winner = Some(w);
+                    reason = Some(r);
+                    break;
+                }
+                GameStatus::Draw(r) => {
+                    reason = Some(r);
+                    break;
+                }
+            }
+            let index: usize = if position.side() == Side::Red { red } else { 1usize - red };
+            position.set_skill(Skill {
+                level: skills[index],
+                seed: seed.wrapping_add((game * moves + plies) as u64),
+            });
+            let result: SearchResult = position.search(limit).unwrap();
+            position.make_move(result.best_move)?;
+            plies += 1usize;
+        }
+        let first: Side = if red == 0usize { Side::Red } else { Side::Black };
+        let outcome: usize = match winner {
+            Some(w) if w == first => 0usize,
+            Some(_) => 2usize,
+            None => 1usize,
+        };
+        results[outcome] += 1usize;
+        println!(
+            "game---number=[{}],red=[{}],black=[{}],winner=[{:?}],reason=[{:?}],moves=[{}]",
+            game + 1usize,
+            skills[red],
+            skills[1usize - red],
+            winner,
+            reason,
+            plies
+        );
+    }
+    let score: f64 = (results[0] as f64 + results[1] as f64 * 0.5f64) / cmp::max(games, 1usize) as f64;
+    println!(
+        "match---skill=[{}],opponent=[{}],wins=[{}],draws=[{}],losses=[{}],score=[{:.3}],elo=[{}],time=[{}ms]",
+        skills[0],
+        skills[1],
+        results[0],
+        results[1],
+        results[2],
+        score,
+        elo_difference(score),
+        start.elapsed().as_millis()
+    );
+    Ok(score)
+}
+
+/// 级别阶梯
+///
+/// 相邻的两个级别（低的一方为测定对象）依次对局，以最强级别为`MAX_SKILL_ELO`，
+/// 累计等级分差打印各级别的等级分。种子和对局数相同时结果相同（`SKILL_LEVELS`的等级分由此测定）。
+///
+/// * `fen` - 开始局面。
+/// * `limit` - 每一步的检索限制。
+/// * `moves` - 每一局的最大步数（半回合）。
+/// * `rule` - 重复局面规则。
+/// * `games` - 每一组的对局数。
+/// * `seed` - 种子。
+/// * `search` - 检索设置。
+fn ladder(
+    fen: &str,
+    limit: SearchLimit,
+    moves: usize,
+    rule: RepetitionRule,
+    games: usize,
+    seed: u64,
+    search: &SearchOptions,
+) -> Result<(), String> {
+    let mut differences: Vec<i32> = Vec::new();
+    for level in 0usize..MAX_SKILL_LEVEL {
+        let score: f64 = play_match(fen, limit, moves, rule, [level, level + 1usize], games, seed, search)?;
+        differences.push(elo_difference(score));
+    }
+    let mut elo: i32 = MAX_SKILL_ELO;
+    println!("ladder---level=[{}],elo=[{}]", MAX_SKILL_LEVEL, elo);
+    for level in (0usize..MAX_SKILL_LEVEL).rev() {
+        elo += differences[level];
+        println!("ladder---level=[{}],elo=[{}],difference=[{}]", level, elo, differences[level]);
+    }
+    Ok(())
+}
+
+/// 等级分差
+///
+/// 由得分率推算的等级分差，全胜或者全负时为±800（得分率限制在1%~99%）。
+///
+/// * `score` - 得分率（0.0~1.0）。
+fn elo_difference(score: f64) -> i32 {
+    let score: f64 = score.clamp(0.01f64, 0.99f64);
+    (-400f64 * (1f64 / score - 1f64).log10()).round() as i32
+}
+
+/// 命令行选项
+///
+/// `--name value`形式的选项集合。
+struct Options {
+    values: HashMap<String, String>,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Options, String> {
+        let mut values: HashMap<String, String> = HashMap::new();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            if !arg.starts_with("--") {
+                return Err(format!("unexpected argument [{}]", arg));
+            }
+            let value: &String = iter
+                .next()
+                .ok_or_else(|| format!("missing value for [{}]", arg))?;
+            values.insert(arg[2..].to_string(), value.clone());
+        }
+        Ok(Options { values })
+    }
+
+    fn get(&self, name: &str) -> Option<&String> {
+        self.values.get(name)
+    }
+
+    fn string(&self, name: &str, default: &str) -> String {
+        self.get(name).cloned().unwrap_or_else(|| default.to_string())
+    }
+
+    fn number(&self, name: &str, default: u64) -> Result<u64, String> {
+        match self.get(name) {
+            Some(v) => v
+                .parse()
+                .map_err(|_| format!("invalid number [{}] for [--{}]", v, name)),
+            None => Ok(default),
+        }
+    }
+
+    /// `--depth`、`--nodes`和`--time`（毫秒）可以组合使用，都没有指定时为默认深度。
+    fn limit(&self) -> Result<SearchLimit, String> {
+        let mut limit: SearchLimit = SearchLimit::default();
+        if self.get("depth").is_some() {
+            limit.depth = Some(self.number("depth", 0u64)? as usize);
+        }
+        if self.get("nodes").is_some() {
+            limit.nodes = Some(self.number("nodes", 0u64)?);
+        }
+        if self.get("time").is_some() {
+            limit.time = Some(Duration::from_millis(self.number("time", 0u64)?));
+        }
+        if limit == SearchLimit::default() {
+            limit.depth = Some(DEFAULT_DEPTH);
+        }
+        Ok(limit)
+    }
+
+    /// `--rule`（asian或者chinese），没有指定时为亚洲规则。
+    fn rule(&self) -> Result<RepetitionRule, String> {
+        match self.string("rule", "asian").as_str() {
+            "asian" => Ok(RepetitionRule::Asian),
+            "chinese" => Ok(RepetitionRule::Chinese),
+            rule => Err(format!("invalid rule [{}]", rule)),
+        }
+    }
+
+    /// `--seed`，没有指定时使用当前时间。
+    fn seed(&self) -> Result<u64, String> {
+        match self.get("seed") {
+            Some(_) => self.number("seed", 0u64),
+            None => Ok(SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_nanos() as u64)
+                .unwrap_or(0u64)),
+        }
+    }
+
+    /// 棋力级别（0~`MAX_SKILL_LEVEL`），没有指定时为最强。
+    fn level(&self, name: &str) -> Result<usize, String> {
+        let level: usize = self.number(name, MAX_SKILL_LEVEL as u64)? as usize;
+        if level > MAX_SKILL_LEVEL {
+            return Err(format!("[--{}] must be 0..={}", name, MAX_SKILL_LEVEL));
+        }
+        Ok(level)
+    }
+
+    /// `--random`的评价值范围（0以上）。
+    fn margin(&self) -> Result<i32, String> {
+        let text: &str = self.get("random").map_or("0", |v| v.as_str());
+        match text.parse::<i32>() {
+            Ok(margin) if margin >= 0i32 => Ok(margin),
+            Ok(_) => Err(String::from("[--random] must not be negative")),
+            Err(_) => Err(format!("invalid number [{}] for [--random]", text)),
+        }
+    }
+
+    /// `--random`（评价值的范围）、`--skill`和`--seed`。
+    fn engine(&self) -> Result<EngineOptions, String> {
+        if self.get("random").is_none() && self.get("skill").is_none() && self.get("seed").is_some() {
+            return Err(String::from("[--seed] requires [--random] or [--skill]"));
+        }
+        let seed: u64 = self.seed()?;
+        let randomness: Option<Randomness> = match self.get("random") {
+            Some(_) => Some(Randomness {
+                margin: self.margin()?,
+                seed,
+            }),
+            None => None,
+        };
+        let skill: Option<Skill> = match self.get("skill") {
+            Some(_) => Some(Skill {
+                level: self.level("skill")?,
+                seed,
+            }),
+            None => None,
+        };
+        Ok(EngineOptions { randomness, skill })
+    }
+
+    /// 子命令不支持的选项检出（`common`为所有子命令共通的选项）。
+    fn check(&self, allowed: &[&str], common: &[&str]) -> Result<(), String> {
+        match self
+            .values
+            .keys()
+            .find(|k| !allowed.contains(&k.as_str()) && !common.contains(&k.as_str()))
+        {
+            Some(k) => Err(format!("unknown option [--{}]", k)),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_args(args: &[&str]) -> Result<Arguments, String> {
+        parse(&args.iter().map(|a| String::from(*a)).collect::<Vec<String>>())
+    }
+
+    #[test]
+    fn random_margin_is_validated() {
+        match parse_args(&["bestmove", "--random", "30", "--seed", "1"]).unwrap().command {
+            Command::BestMove { engine, .. } => {
+                assert_eq!(engine.randomness.map(|r| (r.margin, r.seed)), Some((30i32, 1u64)))
+            }
+            command => panic!("{:?}", command),
+        }
+        assert_eq!(
+            parse_args(&["bestmove", "--random", "-5"]).unwrap_err(),
+            "[--random] must not be negative"
+        );
+        // 超过i32范围的值不截断，返回错误
+        assert_eq!(
+            parse_args(&["bestmove", "--random", "4294967326"]).unwrap_err(),
+            "invalid number [4294967326] for [--random]"
+        );
+    }
+
+    #[test]
+    fn ladder_defaults_are_fixed() {
+        // 省略时种子和对局数固定，测定可以重现
+        match parse_args(&["ladder", "--depth", "4"]).unwrap().command {
+            Command::Ladder { games, seed, .. } => {
+                assert_eq!((games, seed), (DEFAULT_LADDER_GAMES, DEFAULT_LADDER_SEED))
+            }
+            command => panic!("{:?}", command),
+        }
+        assert!(parse_args(&["ladder", "--skill", "3"]).is_err());
+    }
+}
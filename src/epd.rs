@@ -0,0 +1,180 @@
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::BufReader;
+use std::time::Instant;
+
+use chess::*;
+
+use cli::SearchOptions;
+
+/**************************************************************************************************/
+/*******************************      EPD DEFINITION      *****************************************/
+/**************************************************************************************************/
+// 最佳着法
+const OPERATION_BEST_MOVE: &str = "bm";
+// 避免着法
+const OPERATION_AVOID_MOVE: &str = "am";
+// 局面名称
+const OPERATION_ID: &str = "id";
+
+/// EPD局面
+///
+/// 一行EPD记录，`bm`为应该走的着法，`am`为应该避免的着法（ICCS格式）。
+#[derive(Debug, Clone)]
+pub struct EpdPosition {
+    pub id: String,
+    pub fen: String,
+    pub best_moves: Vec<String>,
+    pub avoid_moves: Vec<String>,
+}
+
+impl EpdPosition {
+    /// EPD解析
+    ///
+    /// 解析一行EPD记录，空行、注释行（#开头）以及没有`bm`/`am`的记录返回None。
+    ///
+    /// * `line` - EPD记录，例：
+    ///   `rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w - - bm h2e2; id "central cannon";`
+    pub fn parse(line: &str) -> Option<EpdPosition> {
+        let line: &str = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let mut fields = line.split_whitespace();
+        let placement: &str = fields.next()?;
+        let side: &str = fields.next()?;
+        let operations: Vec<&str> = fields.skip_while(|field| *field == "-").collect();
+
+        let mut position = EpdPosition {
+            id: String::new(),
+            fen: format!("{} {}", placement, side),
+            best_moves: Vec::new(),
+            avoid_moves: Vec::new(),
+        };
+        for operation in operations.join(" ").split(';') {
+            let mut operands = operation.split_whitespace();
+            match operands.next() {
+                Some(OPERATION_BEST_MOVE) => {
+                    position.best_moves.extend(operands.map(|m| m.to_ascii_lowercase()))
+                }
+                Some(OPERATION_AVOID_MOVE) => {
+                    position.avoid_moves.extend(operands.map(|m| m.to_ascii_lowercase()))
+                }
+                Some(OPERATION_ID) => {
+                    position.id = operands.collect::<Vec<&str>>().join(" ").replace('"', "")
+                }
+                _ => {}
+            }
+        }
+        if position.best_moves.is_empty() && position.avoid_moves.is_empty() {
+            None
+        } else {
+            Some(position)
+        }
+    }
+
+    /// 解答判定
+    ///
+    /// 着法在`bm`之中，并且不在`am`之中时判定为解答成功。
+    ///
+    /// * `iccs` - 引擎给出的着法（ICCS格式）。
+    pub fn is_solved(&self, iccs: &str) -> bool {
+        (self.best_moves.is_empty() || self.best_moves.iter().any(|m| m == iccs))
+            && !self.avoid_moves.iter().any(|m| m == iccs)
+    }
+}
+
+/// EPD测试执行
+///
+/// 读取EPD文件，对所有局面检索并判定，打印每个局面的结果和汇总，返回【成功数, 失败数】。
+///
+/// * `epd_file` - EPD文件路径。
+/// * `limit` - 检索限制。
+/// * `search` - 检索设置。
+pub fn run(epd_file: &str, limit: SearchLimit, search: SearchOptions) -> (usize, usize) {
+    let file = File::open(epd_file).expect("Unable to open file");
+    let mut solved = 0usize;
+    let mut failed = 0usize;
+    let start: Instant = Instant::now();
+
+    for (number, line) in BufReader::new(file).lines().enumerate() {
+        let line: String = line.expect("Unable to read file");
+        let position: EpdPosition = match EpdPosition::parse(&line) {
+            Some(p) => p,
+            None => continue,
+        };
+
+        let result: String = match Position::from_fen(&position.fen) {
+            Ok(mut p) => {
+                search.apply(&mut p);
+                match p.search(limit) {
+                    Some(r) => r.best_move.to_iccs(),
+                    None => String::from("none"),
+                }
+            }
+            Err(message) => message,
+        };
+
+        let is_solved: bool = position.is_solved(&result);
+        if is_solved {
+            solved += 1;
+        } else {
+            failed += 1;
+        }
+        println!(
+            "{}---id=[{}],line=[{}],bm=[{}],am=[{}],result=[{}]",
+            if is_solved { "solved" } else { "failed" },
+            position.id,
+            number + 1,
+            position.best_moves.join(" "),
+            position.avoid_moves.join(" "),
+            result
+        );
+    }
+
+    println!(
+        "summary---solved=[{}],failed=[{}],total=[{}],time=[{}ms]",
+        solved,
+        failed,
+        solved + failed,
+        start.elapsed().as_millis()
+    );
+    (solved, failed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_reads_operations() {
+        let position: EpdPosition =
+            EpdPosition::parse("4k4/R8/9/9/9/9/9/9/9/3K4R w - - bm I0I9 a8f8; am a8a9; id \"double rook mate\";")
+                .unwrap();
+        assert_eq!(position.fen, "4k4/R8/9/9/9/9/9/9/9/3K4R w");
+        assert_eq!(position.best_moves, vec!["i0i9", "a8f8"]);
+        assert_eq!(position.avoid_moves, vec!["a8a9"]);
+        assert_eq!(position.id, "double rook mate");
+
+        // 空行、注释以及没有bm/am的记录
+        assert!(EpdPosition::parse("").is_none());
+        assert!(EpdPosition::parse("# 4k4/9/9/9/9/9/9/9/9/3K4R w - - bm i0i9;").is_none());
+        assert!(EpdPosition::parse("4k4/9/9/9/9/9/9/9/9/3K4R w - - id \"no moves\";").is_none());
+        assert!(EpdPosition::parse("4k4/9/9/9/9/9/9/9/9/3K4R").is_none());
+    }
+
+    #[test]
+    fn is_solved_checks_best_and_avoid_moves() {
+        let best: EpdPosition = EpdPosition::parse("3k5/9/9/9/r8/9/9/9/9/R3K4 w - - bm a0a5 a0a1;").unwrap();
+        assert!(best.is_solved("a0a5"));
+        assert!(best.is_solved("a0a1"));
+        assert!(!best.is_solved("e0e1"));
+
+        let avoid: EpdPosition = EpdPosition::parse("r2k5/9/9/9/p8/9/9/9/9/R3K4 w - - am a0a5;").unwrap();
+        assert!(avoid.is_solved("e0e1"));
+        assert!(!avoid.is_solved("a0a5"));
+
+        let both: EpdPosition = EpdPosition::parse("r2k5/9/9/9/p8/9/9/9/9/R3K4 w - - bm a0a5; am a0a5;").unwrap();
+        assert!(!both.is_solved("a0a5"));
+    }
+}
@@ -1,8 +1,12 @@
-use game::Side::Black;
-use serde;
-use serde_derive;
 use std::cmp;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use params::{EvaluateParams, KingSafetyParams};
+use tt::{Bound, TranspositionTable, TtEntry, DEFAULT_HASH_SIZE, MAX_HASH_SIZE};
 
 // 是否打印计算过程
 const DEBUG_MODE: bool = false;
@@ -11,19 +15,56 @@ const DEBUG_MODE: bool = false;
 /*******************************     BASIC DEFINITION     *****************************************/
 /**************************************************************************************************/
 // 横向
-const WIDTH: usize = 9;
+pub const WIDTH: usize = 9;
 // 纵向
-const HEIGHT: usize = 10;
+pub const HEIGHT: usize = 10;
 // 最大位置数
-const MAX_CELLS_SIZE: usize = WIDTH * HEIGHT;
-// 最大棋子数
-const MAX_PIECES_SIZE: usize = 32;
+pub const MAX_CELLS_SIZE: usize = WIDTH * HEIGHT;
 
 const STEP_INCREASE: bool = true;
 const STEP_DECREASE: bool = false;
 const PROCESS_ROW: bool = true;
 const PROCESS_COLUMN: bool = false;
 
+// 迭代加深的最大深度
+const MAX_SEARCH_DEPTH: usize = 32;
+// 检索中每隔多少个节点检查一次停止标志、节点数和时间（2的幂减1）
+const LIMIT_CHECK_MASK: u64 = 1023;
+// 空着剪枝的最小深度和减少的深度
+const NULL_MOVE_MIN_DEPTH: usize = 3;
+const NULL_MOVE_REDUCTION: usize = 2;
+// 后期着法减少深度（LMR）的最小深度，以及不减少深度的着法数
+const LMR_MIN_DEPTH: usize = 3;
+const LMR_FULL_MOVES: usize = 3;
+// 无益剪枝的评价值余量（按剩余深度，深度1、2有效）
+const FUTILITY_MARGINS: [i32; 3] = [0, 120, 320];
+// 渴望窗口：开始使用的深度、初始的半宽、失败时半宽的扩大倍数，半宽超过上限时失败的一侧不再限制
+const ASPIRATION_MIN_DEPTH: usize = 3;
+const ASPIRATION_WINDOW: i32 = 50;
+const ASPIRATION_WIDEN: i32 = 4;
+const ASPIRATION_MAX_WINDOW: i32 = 1000;
+// 不限制的窗口（走棋方视角的上限，下限为其相反数）
+const INFINITE_WINDOW: i32 = i32::MAX;
+
+// 将死的评价值（减去到将死为止的步数）
+pub const MATE_VALUE: i32 = 900000;
+// 评价值的绝对值大于该值时为将死
+pub const MATE_BOUND: i32 = MATE_VALUE - MAX_SEARCH_DEPTH as i32 * 4;
+
+// 重复局面判负时的评价值（小于将死的评价值）
+const REPETITION_LOSS_VALUE: i32 = 500000;
+// 对局中判定重复局面的出现次数（检索中第二次出现就判定）
+const GAME_REPETITION_COUNT: usize = 3;
+
+// 自然限着：双方120步（60回合）没有吃子时判和
+pub const MOVE_LIMIT_PLIES: usize = 120;
+
+// 有攻击能力的棋子（马、车、炮、兵），双方都没有时判和
+const ATTACKING_PIECES: &str = "NRCPnrcp";
+
+// 初始局面
+pub const START_FEN: &str = "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w";
+
 // 帅 士 相 马 车 炮 兵
 const RED_KING: char = 'K';
 const RED_ADVISER: char = 'A';
@@ -85,80 +126,32 @@ const RED_KING_POSITIONS: [usize; 9] = [66, 67, 68, 75, 76, 77, 84, 85, 86];
 const BLACK_KING_POSITIONS: [usize; 9] = [3, 4, 5, 12, 13, 14, 21, 22, 23];
 
 /**************************************************************************************************/
-/*******************************   EVALUATE DEFINITION    *****************************************/
+/*******************************   ZOBRIST DEFINITION     *****************************************/
 /**************************************************************************************************/
-// 帅 士 相 马 车 炮 兵
-const EVALUATE_BASIC: [i32; 7] = [1000000, 110, 110, 300, 600, 300, 70];
-
-//const EVALUATE_KING: [i32; MAX_CELLS_SIZE] = [
-//    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-//    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-//    0, 0, -9, -9, -9, 0, 0, 0, 0, 0, 0, -8, -8, -8, 0, 0, 0, 0, 0, 0, 1, 5, 1, 0, 0, 0,
-//];
-//
-//const EVALUATE_ADVISER: [i32; MAX_CELLS_SIZE] = [
-//    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-//    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-//    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-//];
-//
-//const EVALUATE_BISHOP: [i32; MAX_CELLS_SIZE] = [
-//    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-//    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-//    -2, 0, 0, 0, 3, 0, 0, 0, -2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-//];
-
-const EVALUATE_KNIGHT: [i32; MAX_CELLS_SIZE] = [
-    4, 8, 16, 12, 4, 12, 16, 8, 4,
-    4, 10, 28, 16, 8, 16, 28, 10, 4,
-    12, 14, 16, 20, 18, 20, 16, 14, 12,
-    8, 24, 18, 24, 20, 24, 18, 24, 8,
-    6, 16, 14, 18, 16, 18, 14, 16, 6,
-    4, 12, 16, 14, 12, 14, 16, 12, 4,
-    2, 6, 8, 6, 10, 6, 8, 6, 2,
-    4, 2, 8, 8, 4, 8, 8, 2, 4,
-    0, 2, 4, 4, -2, 4, 4, 2, 0,
-    0, -4, 0, 0, 0, 0, 0, -4, 0
-];
-
-const EVALUATE_ROOK: [i32; MAX_CELLS_SIZE] = [
-    14, 14, 12, 18, 16, 18, 12, 14, 14,
-    16, 20, 18, 24, 26, 24, 18, 20, 16,
-    12, 12, 12, 18, 18, 18, 12, 12, 12,
-    12, 18, 16, 22, 22, 22, 16, 18, 12,
-    12, 14, 12, 18, 18, 18, 12, 14, 12,
-    12, 16, 14, 20, 20, 20, 14, 16, 12,
-    6, 10, 8, 14, 14, 14, 8, 10, 6,
-    4, 8, 6, 14, 12, 14, 6, 8, 4,
-    8, 4, 8, 16, 8, 16, 8, 4, 8,
-    -2, 10, 6, 14, 12, 14, 6, 10, -2
-];
-
-const EVALUATE_CANNON: [i32; MAX_CELLS_SIZE] = [
-    6, 4, 0, -10, -12, -10, 0, 4, 6,
-    2, 2, 0, -4, -14, -4, 0, 2, 2,
-    2, 2, 0, -10, -8, -10, 0, 2, 2,
-    0, 0, -2, 4, 10, 4, -2, 0, 0,
-    0, 0, 0, 2, 8, 2, 0, 0, 0,
-    -2, 0, 4, 2, 6, 2, 4, 0, -2,
-    0, 0, 0, 2, 4, 2, 0, 0, 0,
-    4, 0, 8, 6, 10, 6, 8, 0, 4,
-    0, 2, 4, 6, 6, 6, 4, 2, 0,
-    0, 0, 2, 6, 6, 6, 2, 0, 0
-];
+// 棋子的Zobrist下标
+const ZOBRIST_PIECES: &str = "KABNRCPkabnrcp";
+// 黑方走棋时的随机数（置换表的键值用，重复局面的键值不包含走棋方）
+const ZOBRIST_BLACK: u64 = 0x7A6B_D2E1_3F08_C495;
 
-const EVALUATE_PAWN: [i32; MAX_CELLS_SIZE] = [
-    0, 3, 6, 9, 12, 9, 6, 3, 0,
-    18, 36, 56, 80, 120, 80, 56, 36, 18,
-    14, 26, 42, 60, 80, 60, 42, 26, 14,
-    10, 20, 30, 34, 40, 34, 30, 20, 10,
-    6, 12, 18, 18, 20, 18, 18, 12, 6,
-    2, 0, 8, 0, 8, 0, 8, 0, 2,
-    0, 0, -2, 0, 4, 0, -2, 0, 0,
-    0, 0, 0, 0, 0, 0, 0, 0, 0,
-    0, 0, 0, 0, 0, 0, 0, 0, 0,
-    0, 0, 0, 0, 0, 0, 0, 0, 0
-];
+lazy_static! {
+    // 棋子×位置的随机数（固定种子，每次启动相同）
+    static ref ZOBRIST: Vec<[u64; MAX_CELLS_SIZE]> = {
+        let mut seed: u64 = 0x9E37_79B9_7F4A_7C15;
+        let mut table: Vec<[u64; MAX_CELLS_SIZE]> = Vec::new();
+        for _ in 0..ZOBRIST_PIECES.len() {
+            let mut keys = [0u64; MAX_CELLS_SIZE];
+            for key in keys.iter_mut() {
+                // xorshift64*
+                seed ^= seed >> 12;
+                seed ^= seed << 25;
+                seed ^= seed >> 27;
+                *key = seed.wrapping_mul(0x2545_F491_4F6C_DD1D);
+            }
+            table.push(keys);
+        }
+        table
+    };
+}
 
 /**************************************************************************************************/
 /*******************************  PIECE MOVES DEFINITION  *****************************************/
@@ -224,34 +217,448 @@ lazy_static! {
 }
 
 // By default, player is Red, and computer is Black.
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
-enum Side {
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+pub enum Side {
     Red,
     Black,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
-struct MinMaxNode {
-    piece: char,
-    from: usize,
-    to: usize,
+impl Side {
+    /// FEN走棋方取得
+    ///
+    /// 返回FEN中的走棋方（红方为w，黑方为b）。
+    pub fn to_fen(self) -> &'static str {
+        match self {
+            Side::Red => "w",
+            Side::Black => "b",
+        }
+    }
+
+    /// 对方取得
+    ///
+    /// 返回对方的颜色。
+    pub fn opponent(&self) -> Side {
+        match self {
+            Side::Red => Side::Black,
+            Side::Black => Side::Red,
+        }
+    }
+}
+
+/// 重复局面规则
+///
+/// 亚洲规则（AXF）和中国规则的区别：中国规则中捉过河兵（卒）不算捉。
+/// 两种规则中帅（将）、兵（卒）捉子都不算捉，长将长捉的一方判负，双方同类则判和，长将对长捉时长将方判负。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RepetitionRule {
+    Asian,
+    Chinese,
+}
+
+/// 着法分类（重复局面判定用）
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum RepetitionKind {
+    // 闲着
+    Idle,
+    // 长捉（包含一将一捉）
+    Chase,
+    // 长将
+    Check,
+}
+
+/// 重复局面判定结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RepetitionResult {
+    // 和棋
+    Draw,
+    // 指定方违例判负，以及违例的种类
+    Loss(Side, RepetitionKind),
+}
+
+/// 着法历史
+#[derive(Debug, Clone, Copy)]
+struct HistoryEntry {
+    // 着法执行前的局面键值
+    key: u64,
+    node: Move,
+    captured: Option<char>,
 }
 
-impl MinMaxNode {
-    fn new(p: char, f: usize, t: usize) -> MinMaxNode {
-        MinMaxNode {
+/// 帅（将）的安全性评价项目的名称
+pub const KING_SAFETY_TERMS: [&str; 8] = [
+    "missing_adviser",
+    "missing_bishop",
+    "rook_on_king_file",
+    "central_cannon",
+    "knight_near_palace",
+    "palace_attacker",
+    "palace_defender",
+    "king_open_file",
+];
+
+/// 棋子的评价明细（该棋子一方视角）
+#[derive(Debug, Clone, Copy)]
+pub struct PieceTrace {
+    pub piece: char,
+    pub square: usize,
+    /// 棋子价值（中局、残局）。
+    pub material: [i32; 2],
+    /// 位置分（中局、残局，已乘以倍率）。
+    pub position: [i32; 2],
+    pub mobility: i32,
+    pub cannon_platform: i32,
+    pub rook_file: i32,
+}
+
+impl PieceTrace {
+    /// ICCS坐标转换
+    ///
+    /// 将棋子位置转换成ICCS格式（例：e0）。
+    pub fn to_iccs(&self) -> String {
+        let mut iccs: String = String::new();
+        iccs.push((b'a' + INDEX_COLUMN[self.square] as u8) as char);
+        iccs.push((b'9' - INDEX_ROW[self.square] as u8) as char);
+        iccs
+    }
+
+    /// 活动性合计
+    pub fn activity(&self) -> i32 {
+        self.mobility + self.cannon_platform + self.rook_file
+    }
+}
+
+/// 一方的评价明细（该方视角）
+#[derive(Debug, Clone)]
+pub struct SideTrace {
+    pub side: Side,
+    pub pieces: Vec<PieceTrace>,
+    /// 帅（将）的安全性（顺序与`KING_SAFETY_TERMS`相同，只在中局有效）。
+    pub king_safety: [i32; 8],
+}
+
+impl SideTrace {
+    fn new(side: Side, king_safety: [i32; 8]) -> SideTrace {
+        SideTrace {
+            side,
+            pieces: Vec::new(),
+            king_safety,
+        }
+    }
+
+    /// 中局合计（棋子价值、位置分以及帅（将）的安全性）
+    pub fn middlegame(&self) -> i32 {
+        let pieces: i32 = self.pieces.iter().map(|p| p.material[0] + p.position[0]).sum();
+        pieces + self.king_safety.iter().sum::<i32>()
+    }
+
+    /// 残局合计（棋子价值、位置分）
+    pub fn endgame(&self) -> i32 {
+        self.pieces.iter().map(|p| p.material[1] + p.position[1]).sum()
+    }
+
+    /// 活动性合计
+    pub fn activity(&self) -> i32 {
+        self.pieces.iter().map(|p| p.activity()).sum()
+    }
+}
+
+/// 评价明细
+#[derive(Debug, Clone)]
+pub struct EvaluateTrace {
+    pub red: SideTrace,
+    pub black: SideTrace,
+    /// 阶段值（`total_phase`为中局，0为残局）。
+    pub phase: i32,
+    pub total_phase: i32,
+    /// 评价值（黑方视角，与检索使用的评价值相同）。
+    pub score: i32,
+}
+
+/// 检索限制
+///
+/// 深度（包含根节点的一步）、节点数、时间以及无限检索可以组合使用，都没有指定时检索到最大深度。
+/// 只指定深度且不使用渴望窗口时以该深度检索一次，否则迭代加深。节点数和时间在检索中途也会检查，
+/// 超过时中止当前的一层并使用最后完成一层的结果，超过时间后也不再开始下一层。
+/// 无限检索找到将死后也继续加深，直到停止标志被设置或者达到最大深度。
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SearchLimit {
+    pub depth: Option<usize>,
+    pub nodes: Option<u64>,
+    pub time: Option<Duration>,
+    pub infinite: bool,
+}
+
+impl SearchLimit {
+    /// 固定深度
+    pub fn depth(depth: usize) -> SearchLimit {
+        SearchLimit { depth: Some(depth), ..SearchLimit::default() }
+    }
+
+    /// 限定节点数
+    pub fn nodes(nodes: u64) -> SearchLimit {
+        SearchLimit { nodes: Some(nodes), ..SearchLimit::default() }
+    }
+
+    /// 限定时间
+    pub fn time(time: Duration) -> SearchLimit {
+        SearchLimit { time: Some(time), ..SearchLimit::default() }
+    }
+
+    /// 无限检索（直到停止标志被设置）
+    pub fn infinite() -> SearchLimit {
+        SearchLimit { infinite: true, ..SearchLimit::default() }
+    }
+
+    /// 只指定了深度时为true
+    fn is_fixed_depth(&self) -> bool {
+        self.depth.is_some() && self.nodes.is_none() && self.time.is_none() && !self.infinite
+    }
+}
+
+/// 着法随机化
+///
+/// 根节点评价值与最佳着法相差`margin`以内的着法中随机选择（将死时不随机），
+/// 相同的`seed`和相同的着法顺序时选择结果相同。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Randomness {
+    pub margin: i32,
+    pub seed: u64,
+}
+
+/// 根节点着法的检索结果
+///
+/// 根节点的所有着法都以同一个窗口检索，评价值在窗口以内时为准确值。
+/// 使用渴望窗口时窗口以外的着法的评价值为窗口的边界（多变例和着法随机化时不使用渴望窗口）。
+#[derive(Debug, Clone, PartialEq)]
+pub struct RootMove {
+    pub node: Move,
+    /// 评价值（走棋方视角）。
+    pub score: i32,
+    /// 主要变例（从该着法开始）。
+    pub pv: Vec<Move>,
+}
+
+/// 渴望窗口的检索失败
+///
+/// 迭代加深中根节点的最佳值超出以上一层的评价值为中心的窗口时记录，之后扩大窗口重新检索同一深度。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AspirationFail {
+    /// 深度（包含根节点的一步）。
+    pub depth: usize,
+    /// 超过窗口上限（fail high）时为true，低于窗口下限（fail low）时为false。
+    pub high: bool,
+    /// 评价值（走棋方视角），准确值在此以上（fail high）或者以下（fail low）。
+    pub score: i32,
+    /// 失败时的窗口（走棋方视角的下限和上限）。
+    pub window: (i32, i32),
+}
+
+/// 棋力级别
+///
+/// `level`为0（最弱）~`MAX_SKILL_LEVEL`（最强，不限制），`seed`为评价噪声和着法随机化的种子。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Skill {
+    pub level: usize,
+    pub seed: u64,
+}
+
+/// 选择性检索
+///
+/// 各剪枝、延伸方法以及PVS的开关（默认全部有效），测试和比较用。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Selectivity {
+    /// 空着剪枝（被将军以及没有车马炮时不使用）。
+    pub null_move: bool,
+    /// 后期着法减少深度（LMR，只对不吃子、不将军的着法）。
+    pub late_move_reduction: bool,
+    /// 前沿节点的无益剪枝（深度1、2的不吃子、不将军的着法）。
+    pub futility: bool,
+    /// 将军延伸以及唯一应着延伸。
+    pub extension: bool,
+    /// 主要变例检索（PVS，第二个以后的着法以零窗口检索）。
+    pub pvs: bool,
+    /// 根节点的渴望窗口（固定深度时也迭代加深）。
+    pub aspiration: bool,
+}
+
+impl Selectivity {
+    /// 全部有效
+    pub fn all() -> Selectivity {
+        Selectivity {
+            null_move: true,
+            late_move_reduction: true,
+            futility: true,
+            extension: true,
+            pvs: true,
+            aspiration: true,
+        }
+    }
+
+    /// 全部无效（全宽检索）
+    pub fn none() -> Selectivity {
+        Selectivity {
+            null_move: false,
+            late_move_reduction: false,
+            futility: false,
+            extension: false,
+            pvs: false,
+            aspiration: false,
+        }
+    }
+
+    /// 解析
+    ///
+    /// `all`、`none`或者逗号分隔的`null`、`lmr`、`futility`、`ext`、`pvs`、`asp`（指定的有效）。
+    ///
+    /// * `text` - 文字列。
+    pub fn parse(text: &str) -> Result<Selectivity, String> {
+        match text {
+            "all" => return Ok(Selectivity::all()),
+            "none" => return Ok(Selectivity::none()),
+            _ => {}
+        }
+        let mut selectivity: Selectivity = Selectivity::none();
+        for name in text.split(',') {
+            match name.trim() {
+                "null" => selectivity.null_move = true,
+                "lmr" => selectivity.late_move_reduction = true,
+                "futility" => selectivity.futility = true,
+                "ext" => selectivity.extension = true,
+                "pvs" => selectivity.pvs = true,
+                "asp" => selectivity.aspiration = true,
+                _ => return Err(format!("invalid pruning [{}]", name)),
+            }
+        }
+        Ok(selectivity)
+    }
+}
+
+// 检索线程数的上限
+pub const MAX_SEARCH_THREADS: usize = 64;
+
+// 最强的棋力级别（不限制）
+pub const MAX_SKILL_LEVEL: usize = 10;
+
+// 各棋力级别的限制：【最大深度, 节点数, 评价噪声, 随机选择的评价值范围, 等级分】
+// 等级分为`chess ladder --depth 4 --games 40 --seed 1`的测定值（相邻级别对局40局后累计，最强级别为`MAX_SKILL_ELO`）
+// 节点数超过后不再开始下一层（迭代加深）
+const SKILL_LEVELS: [(usize, u64, i32, i32, i32); MAX_SKILL_LEVEL] = [
+    (1, 2_000, 100, 300, -85),
+    (1, 5_000, 50, 100, 130),
+    (2, 20_000, 50, 200, 642),
+    (2, 20_000, 30, 80, 897),
+    (2, 20_000, 10, 20, 1005),
+    (3, 100_000, 50, 120, 1412),
+    (3, 100_000, 20, 40, 1976),
+    (3, 100_000, 5, 10, 1967),
+    (4, 400_000, 30, 60, 2146),
+    (4, 800_000, 10, 20, 2244),
+];
+// 最强级别的等级分（`chess ladder`的基准）
+pub const MAX_SKILL_ELO: i32 = 2200;
+
+impl Skill {
+    /// 等级分
+    ///
+    /// 以最强级别为基准的自我对局测定值，只表示级别之间的相对强弱。
+    pub fn elo(&self) -> i32 {
+        match SKILL_LEVELS.get(self.level) {
+            Some(limit) => limit.4,
+            None => MAX_SKILL_ELO,
+        }
+    }
+}
+
+/// 着法
+///
+/// 位置为0~89，0为黑方底线左端（ICCS的a9），89为红方底线右端（ICCS的i0）。
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct Move {
+    pub piece: char,
+    pub from: usize,
+    pub to: usize,
+}
+
+impl Move {
+    fn new(p: char, f: usize, t: usize) -> Move {
+        Move {
             piece: p,
             from: f,
             to: t,
         }
     }
+
+    /// 空着（检索中让对方连走两步时记录在历史中）
+    fn null() -> Move {
+        Move::new(' ', 0usize, 0usize)
+    }
+
+    fn is_null(&self) -> bool {
+        self.from == self.to
+    }
+
+    /// ICCS坐标转换
+    ///
+    /// 将着法转换成ICCS格式（例：h2e2），列为a~i，行为0~9（红方底线为0）。
+    pub fn to_iccs(&self) -> String {
+        let mut iccs: String = String::new();
+        for position in &[self.from, self.to] {
+            iccs.push((b'a' + INDEX_COLUMN[*position] as u8) as char);
+            iccs.push((b'9' - INDEX_ROW[*position] as u8) as char);
+        }
+        iccs
+    }
+
+    /// 转换
+    ///
+    /// 将着法转换成4位字符串，分别代表【开始.x, 开始.y, 结束.x, 结束.y】，坐标值从0开始。
+    pub fn to_coordinate(&self) -> String {
+        let mut position: String = String::new();
+        position.push_str(&INDEX_ROW[self.from].to_string());
+        position.push_str(&INDEX_COLUMN[self.from].to_string());
+        position.push_str(&INDEX_ROW[self.to].to_string());
+        position.push_str(&INDEX_COLUMN[self.to].to_string());
+        position
+    }
 }
 
+#[derive(Clone)]
 pub struct Board {
     pieces_count: usize,
     positions: [Option<char>; MAX_CELLS_SIZE],
     cache_red_king: usize,
     cache_black_king: usize,
+    side: Side,
+    nodes: u64,
+    score: i32,
+    key: u64,
+    history: Vec<HistoryEntry>,
+    rule: RepetitionRule,
+    initial_plies: usize,
+    root_ply: usize,
+    depth: usize,
+    params: Arc<EvaluateParams>,
+    random_margin: i32,
+    random_state: u64,
+    max_depth: usize,
+    max_nodes: u64,
+    eval_noise: i32,
+    noise_seed: u64,
+    threads: usize,
+    hash_size: usize,
+    table: Option<Arc<TranspositionTable>>,
+    stop: Arc<AtomicBool>,
+    halt: Arc<AtomicBool>,
+    main_thread: bool,
+    aborted: bool,
+    deadline: Option<Instant>,
+    node_limit: Option<u64>,
+    searched: u64,
+    selectivity: Selectivity,
+    pv: Vec<Vec<Move>>,
+    principal: Vec<Move>,
+    root_moves: Vec<RootMove>,
+    aspiration_fails: Vec<AspirationFail>,
 }
 
 impl Board {
@@ -265,6 +672,37 @@ impl Board {
             positions: [None; MAX_CELLS_SIZE],
             cache_red_king: 0usize,
             cache_black_king: 0usize,
+            side: Side::Black,
+            nodes: 0u64,
+            score: 0i32,
+            key: 0u64,
+            history: Vec::new(),
+            rule: RepetitionRule::Asian,
+            initial_plies: 0usize,
+            root_ply: 0usize,
+            depth: 0usize,
+            params: Arc::new(EvaluateParams::default()),
+            random_margin: 0i32,
+            random_state: 0u64,
+            max_depth: 0usize,
+            max_nodes: 0u64,
+            eval_noise: 0i32,
+            noise_seed: 0u64,
+            threads: 1usize,
+            hash_size: DEFAULT_HASH_SIZE,
+            table: None,
+            stop: Arc::new(AtomicBool::new(false)),
+            halt: Arc::new(AtomicBool::new(false)),
+            main_thread: true,
+            aborted: false,
+            deadline: None,
+            node_limit: None,
+            searched: 0u64,
+            selectivity: Selectivity::all(),
+            pv: vec![Vec::new(); MAX_SEARCH_DEPTH + 2usize],
+            principal: Vec::new(),
+            root_moves: Vec::new(),
+            aspiration_fails: Vec::new(),
         }
     }
 
@@ -272,195 +710,1268 @@ impl Board {
     /// 初始化棋盘
     ///
     /// 根据FEN初始化棋盘。
+    /// 第二个字段为走棋方（w或r为红方，b为黑方），省略时默认为黑方（电脑）走棋。
     ///
     /// * `fen` - FEN字符串。
     pub fn init_board(&mut self, fen: &str) {
-        // rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR
+        // rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR b
         self.clear();
+        let mut fields = fen.split_whitespace();
+        let placement: &str = fields.next().unwrap_or("");
         let mut position = 0usize;
-        let mut pieces = 0usize;
-        for c in fen.chars() {
+        for c in placement.chars() {
             match c {
                 '/' => {}
-                '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' => {
+                '1'..='9' => {
                     position += c.to_digit(10).unwrap() as usize;
                 }
                 _ => {
-                    self.positions[position] = Some(c);
+                    if position < MAX_CELLS_SIZE {
+                        self.positions[position] = Some(c);
+                        self.pieces_count += 1;
+                        self.key ^= zobrist(c, position);
+                    }
                     position += 1;
-                    self.pieces_count += 1;
                 }
             };
         }
+        self.side = match fields.next() {
+            Some("w") | Some("r") => Side::Red,
+            _ => Side::Black,
+        };
+        // 第5个字段为没有吃子的步数（省略时为0）
+        self.initial_plies = fields.nth(2).and_then(|f| f.parse().ok()).unwrap_or(0usize);
     }
 
-    /// 检索
+    /// 默认深度
     ///
-    /// 检索下一步，返回移动坐标,
-    /// 返回值为字符串类型，长度为4，分别代表【开始.x, 开始.y, 结束.x, 结束.y】，坐标值从0开始
-    pub fn search(&mut self) -> String {
-        let search_depth: usize = match self.pieces_count {
-            0..=4 => 6,
-            5..=6 => 5,
-            7..=16 => 4,
-            17..=28 => 3,
-            _ => 2,
-        };
+    /// 根据棋子数决定检索深度，棋子越少越深。
+    pub fn default_depth(&self) -> usize {
+        // 深度包含根节点的一步
+        match self.pieces_count {
+            0..=4 => 7,
+            5..=6 => 6,
+            7..=16 => 5,
+            17..=28 => 4,
+            _ => 3,
+        }
+    }
+
+    /// 固定深度检索
+    ///
+    /// 以固定深度检索当前走棋方的最佳着法，无着法时返回None。
+    ///
+    /// * `depth` - 深度（包含根节点的一步，最小为1）。
+    pub fn search_by_depth(&mut self, depth: usize) -> Option<Move> {
+        self.search_by_window(depth, (-INFINITE_WINDOW, INFINITE_WINDOW))
+    }
 
-        let mut best_move: Option<MinMaxNode> = None;
-        let mut best_value = i32::min_value();
-        let mut all_moves: Vec<MinMaxNode> = self.generate_all_moves(&Side::Black);
+    /// 指定窗口的固定深度检索
+    ///
+    /// 根节点的所有着法以指定的窗口检索，最佳值在窗口以外时评价值为窗口的边界。
+    ///
+    /// * `depth` - 深度（包含根节点的一步，最小为1）。
+    /// * `window` - 窗口（走棋方视角的下限和上限）。
+    fn search_by_window(&mut self, depth: usize, window: (i32, i32)) -> Option<Move> {
+        let depth: usize = cmp::max(depth, 1usize);
+        // 没有设置置换表时在第一次检索时分配，之后的检索继续使用
+        if self.table.is_none() {
+            self.table = Some(Arc::new(TranspositionTable::with_size(self.hash_size)));
+        }
+        if self.threads > 1usize {
+            return self.search_parallel(depth, window);
+        }
+        self.search_root(depth, 0usize, window)
+    }
+
+    /// 并行检索（Lazy SMP）
+    ///
+    /// 辅助线程使用棋盘的副本和共享的置换表检索同一个根节点（深度和根节点着法的顺序各不相同），
+    /// 主线程完成后停止辅助线程，返回主线程的最佳着法，节点数为所有线程的合计。
+    /// 窗口只用于主线程，辅助线程不限制窗口。
+    ///
+    /// * `depth` - 深度（包含根节点的一步）。
+    /// * `window` - 窗口（走棋方视角的下限和上限）。
+    fn search_parallel(&mut self, depth: usize, window: (i32, i32)) -> Option<Move> {
+        self.stop.store(false, Ordering::Relaxed);
+        // 停止标志、节点数和时间只由主线程检查
+        let helpers: Vec<Board> = (1usize..self.threads)
+            .map(|_| {
+                let mut helper: Board = self.clone();
+                helper.main_thread = false;
+                helper
+            })
+            .collect();
+        let (best_move, helper_nodes) = thread::scope(|scope| {
+            let handles: Vec<thread::ScopedJoinHandle<u64>> = helpers
+                .into_iter()
+                .enumerate()
+                .map(|(i, mut helper)| {
+                    scope.spawn(move || {
+                        // 一半的辅助线程深一层，完成后继续加深直到被停止
+                        let mut nodes = 0u64;
+                        let mut helper_depth: usize = depth + i % 2usize;
+                        while !helper.stop.load(Ordering::Relaxed) && helper_depth <= MAX_SEARCH_DEPTH {
+                            helper.search_root(helper_depth, i + 1usize, (-INFINITE_WINDOW, INFINITE_WINDOW));
+                            nodes += helper.nodes;
+                            helper_depth += 1usize;
+                        }
+                        nodes
+                    })
+                })
+                .collect();
+            let best_move: Option<Move> = self.search_root(depth, 0usize, window);
+            self.stop.store(true, Ordering::Relaxed);
+            let nodes: u64 = handles.into_iter().map(|handle| handle.join().unwrap_or(0u64)).sum();
+            (best_move, nodes)
+        });
+        self.stop.store(false, Ordering::Relaxed);
+        self.nodes += helper_nodes;
+        best_move
+    }
+
+    /// 根节点检索
+    ///
+    /// 对所有合法着法以同一个窗口检索，`rotation`不为0时（辅助线程）改变着法的顺序。
+    ///
+    /// * `depth` - 深度（包含根节点的一步，最小为1）。
+    /// * `rotation` - 着法顺序的旋转量。
+    /// * `window` - 窗口（走棋方视角的下限和上限）。
+    fn search_root(&mut self, depth: usize, rotation: usize, window: (i32, i32)) -> Option<Move> {
+        let side: Side = self.side;
+        // 转换成黑方视角的极小值和极大值
+        let (lower, upper) = window;
+        let (min, max) = match side {
+            Side::Black => (upper, lower),
+            Side::Red => (-lower, -upper),
+        };
+        self.nodes = 1u64;
+        self.depth = depth;
+        self.root_ply = self.history.len();
+        let mut best_index: Option<usize> = None;
+        let mut best_value: i32 = 0i32;
+        let mut root_values: Vec<(Move, i32)> = Vec::new();
+        let mut root_lines: Vec<Vec<Move>> = Vec::new();
+        let mut all_moves: Vec<Move> = self.generate_legal_moves(&side);
+        if rotation > 0usize && !all_moves.is_empty() {
+            let length: usize = all_moves.len();
+            all_moves.rotate_left(rotation % length);
+        }
         if DEBUG_MODE {
             self.test_print_all_moves("all_moves", &all_moves);
         }
         while let Some(node) = all_moves.pop() {
-            let position_to_backup: Option<char> = self.temporary_move(node.from, node.to);
-            let value: i32 = self.min_max(search_depth, i32::max_value(), i32::min_value(), &Side::Red);
-            if best_move.is_none() || value >= best_value {
-                best_move = Some(node);
+            let position_to_backup: Option<char> = self.do_move(&node);
+            let value: i32 = self.min_max(depth - 1, min, max, &side.opponent());
+            // 被停止时该着法的评价值不准确，到此为止
+            if self.stop.load(Ordering::Relaxed) {
+                self.undo_move(position_to_backup);
+                break;
+            }
+            // 黑方取极大值，红方取极小值
+            let better: bool = match side {
+                Side::Black => value >= best_value,
+                Side::Red => value <= best_value,
+            };
+            if best_index.is_none() || better {
+                best_index = Some(root_values.len());
                 best_value = value;
             }
-            self.recovery(node.from, node.to, position_to_backup);
+            // 根节点着法加上之后的主要变例
+            let mut line: Vec<Move> = vec![node];
+            line.extend_from_slice(&self.pv[1]);
+            self.undo_move(position_to_backup);
+            root_values.push((node, value));
+            root_lines.push(line);
             if DEBUG_MODE {
                 self.test_print_node("node", &node, value)
             }
         }
-        self.translate(best_move.unwrap().from, best_move.unwrap().to)
+        if let Some(index) = self.random_choice(&root_values, best_value, &side) {
+            best_index = Some(index);
+            best_value = root_values[index].1;
+        }
+        // 评价值为黑方视角，转换成走棋方视角
+        let sign: i32 = match side {
+            Side::Black => 1i32,
+            Side::Red => -1i32,
+        };
+        self.score = sign * best_value;
+        self.principal = match best_index {
+            Some(index) => root_lines[index].clone(),
+            None => Vec::new(),
+        };
+        // 评价值相同时与最佳着法一样后检索的着法优先
+        self.root_moves = root_values
+            .iter()
+            .zip(root_lines)
+            .rev()
+            .map(|((node, value), pv)| RootMove { node: *node, score: sign * value, pv })
+            .collect();
+        self.root_moves.sort_by_key(|root| cmp::Reverse(root.score));
+        best_index.map(|index| root_values[index].0)
     }
 
-    /// 转换
+    /// 限制检索
+    ///
+    /// 按检索限制检索当前走棋方的最佳着法。设置了棋力级别或者使用渴望窗口时总是迭代加深，深度和节点数不超过级别的限制。
+    /// 外部的停止标志（`halt_flag`）被设置时中止检索，不会自动清除。
+    ///
+    /// * `limit` - 检索限制。
+    pub fn search_by_limit(&mut self, limit: SearchLimit) -> Option<Move> {
+        let start: Instant = Instant::now();
+        self.deadline = limit.time.map(|time| start + time);
+        self.node_limit = limit.nodes;
+        self.searched = 0u64;
+        self.aborted = false;
+        self.aspiration_fails.clear();
+        let best_move: Option<Move> = match limit.depth {
+            Some(depth) if limit.is_fixed_depth() && self.max_depth == 0usize && !self.aspiration() => {
+                self.search_by_depth(depth)
+            }
+            _ => self.search_iterative(limit, start),
+        };
+        self.deadline = None;
+        self.node_limit = None;
+        self.stop.store(false, Ordering::Relaxed);
+        best_move
+    }
+
+    /// 迭代加深检索
+    ///
+    /// 从深度1开始加深，中止的一层不使用（第一层也没有完成时使用已检索的着法中的最佳着法）。
+    /// 不是无限检索时，找到将死、超过时间或者超过级别的节点数后不再开始下一层。
+    /// 使用渴望窗口时以上一层的评价值为中心的窗口检索，失败时记录并向失败的一侧扩大窗口重新检索。
+    ///
+    /// * `limit` - 检索限制。
+    /// * `start` - 开始时间。
+    fn search_iterative(&mut self, limit: SearchLimit, start: Instant) -> Option<Move> {
+        let mut max_depth: usize = limit.depth.unwrap_or(MAX_SEARCH_DEPTH).clamp(1usize, MAX_SEARCH_DEPTH);
+        if self.max_depth > 0usize {
+            max_depth = cmp::min(max_depth, self.max_depth);
+        }
+        let mut best_move: Option<Move> = None;
+        let mut completed: Option<(i32, usize, Vec<Move>, Vec<RootMove>)> = None;
+        let aspiration: bool = self.aspiration();
+        for depth in 1usize..=max_depth {
+            let mut delta: i32 = ASPIRATION_WINDOW;
+            let (mut lower, mut upper) = match completed {
+                Some((score, ..))
+                    if aspiration && depth >= ASPIRATION_MIN_DEPTH && score.abs() < MATE_BOUND =>
+                {
+                    (score - delta, score + delta)
+                }
+                _ => (-INFINITE_WINDOW, INFINITE_WINDOW),
+            };
+            let found: Option<Move> = loop {
+                let found: Option<Move> = self.search_by_window(depth, (lower, upper));
+                self.searched += self.nodes;
+                let high: bool = self.score >= upper;
+                if self.aborted || found.is_none() || !high && self.score > lower {
+                    break found;
+                }
+                self.aspiration_fails.push(AspirationFail {
+                    depth,
+                    high,
+                    score: self.score,
+                    window: (lower, upper),
+                });
+                delta *= ASPIRATION_WIDEN;
+                match (high, delta > ASPIRATION_MAX_WINDOW) {
+                    (true, true) => upper = INFINITE_WINDOW,
+                    (true, false) => upper = self.score + delta,
+                    (false, true) => lower = -INFINITE_WINDOW,
+                    (false, false) => lower = self.score - delta,
+                }
+            };
+            if self.aborted {
+                match completed.take() {
+                    Some((score, depth, principal, root_moves)) => {
+                        self.score = score;
+                        self.depth = depth;
+                        self.principal = principal;
+                        self.root_moves = root_moves;
+                    }
+                    None => best_move = found,
+                }
+                break;
+            }
+            best_move = found;
+            if best_move.is_none() {
+                break;
+            }
+            completed = Some((self.score, depth, self.principal.clone(), self.root_moves.clone()));
+            if !limit.infinite
+                && (self.score.abs() > MATE_BOUND
+                    || limit.time.is_some_and(|time| start.elapsed() >= time)
+                    || self.max_depth > 0usize && self.searched >= self.max_nodes)
+            {
+                break;
+            }
+        }
+        self.nodes = self.searched;
+        best_move
+    }
+
+    /// 是否使用渴望窗口
+    ///
+    /// 着法随机化（包括棋力级别）需要所有根节点着法的准确值，不使用。
+    fn aspiration(&self) -> bool {
+        self.selectivity.aspiration && self.random_margin <= 0i32
+    }
+
+    /// 检索限制检查
+    ///
+    /// 外部的停止标志被设置、超过时间或者超过节点数时设置停止标志，中止检索。
+    fn check_limits(&mut self) {
+        let exceeded: bool = self.halt.load(Ordering::Relaxed)
+            || self.deadline.is_some_and(|deadline| Instant::now() >= deadline)
+            || self.node_limit.is_some_and(|nodes| self.searched + self.nodes >= nodes);
+        if exceeded {
+            self.aborted = true;
+            self.stop.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// 停止标志取得
+    ///
+    /// 其它线程设置该标志后检索中止，返回已完成部分的结果。清除由调用方负责。
+    pub fn halt_flag(&self) -> Arc<AtomicBool> {
+        self.halt.clone()
+    }
+
+    /// 着法随机化设置
+    ///
+    /// `margin`为0时不随机（默认），总是选择最佳着法。
+    ///
+    /// * `randomness` - 着法随机化。
+    pub fn set_randomness(&mut self, randomness: Randomness) {
+        self.random_margin = cmp::max(randomness.margin, 0i32);
+        // splitmix64，使相邻的种子也产生不同的序列（xorshift的状态不能为0）
+        let mut state: u64 = randomness.seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        state = (state ^ (state >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        state = (state ^ (state >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        self.random_state = (state ^ (state >> 31)) | 1u64;
+    }
+
+    /// 随机着法选择
+    ///
+    /// 从评价值与最佳值相差`random_margin`以内的根节点着法中随机选择一个并返回其下标，
+    /// 不随机或者最佳值为将死时返回None。
+    ///
+    /// * `root_values` - 根节点的着法和评价值（黑方视角）。
+    /// * `best_value` - 最佳值（黑方视角）。
+    /// * `side` - 走棋方。
+    fn random_choice(&mut self, root_values: &[(Move, i32)], best_value: i32, side: &Side) -> Option<usize> {
+        if self.random_margin <= 0i32 || best_value.abs() > MATE_BOUND {
+            return None;
+        }
+        let sign: i32 = match side {
+            Side::Black => 1i32,
+            Side::Red => -1i32,
+        };
+        let candidates: Vec<usize> = (0usize..root_values.len())
+            .filter(|i| sign * (best_value - root_values[*i].1) <= self.random_margin)
+            .collect();
+        // xorshift64*
+        self.random_state ^= self.random_state >> 12;
+        self.random_state ^= self.random_state << 25;
+        self.random_state ^= self.random_state >> 27;
+        let random: u64 = self.random_state.wrapping_mul(0x2545_F491_4F6C_DD1D);
+        candidates
+            .get((random % cmp::max(candidates.len(), 1usize) as u64) as usize)
+            .copied()
+    }
+
+    /// 棋力级别设置
+    ///
+    /// 限制检索的深度和节点数，在评价值中加入噪声，并在相差不大的着法中随机选择（覆盖`set_randomness`的设置）。
+    /// 级别为`MAX_SKILL_LEVEL`以上时取消限制。
+    ///
+    /// * `skill` - 棋力级别。
+    pub fn set_skill(&mut self, skill: Skill) {
+        match SKILL_LEVELS.get(skill.level) {
+            Some(&(depth, nodes, noise, margin, _)) => {
+                self.max_depth = depth;
+                self.max_nodes = nodes;
+                self.eval_noise = noise;
+                self.noise_seed = skill.seed;
+                self.set_randomness(Randomness { margin, seed: skill.seed });
+            }
+            None => {
+                self.max_depth = 0usize;
+                self.max_nodes = 0u64;
+                self.eval_noise = 0i32;
+                self.random_margin = 0i32;
+            }
+        }
+    }
+
+    /// 主要变例更新
+    ///
+    /// 该局面的主要变例为`node`加上下一步局面的主要变例。
+    ///
+    /// * `ply` - 该局面距根节点的步数。
+    /// * `node` - 评价值改善的着法。
+    fn update_pv(&mut self, ply: usize, node: Move) {
+        let (head, tail) = self.pv.split_at_mut(ply + 1usize);
+        head[ply].clear();
+        head[ply].push(node);
+        head[ply].extend_from_slice(&tail[0]);
+    }
+
+    /// 主要变例取得
+    ///
+    /// 返回上一次检索的主要变例（从最佳着法开始，置换表命中时可能不完整）。
+    pub fn principal_variation(&self) -> &[Move] {
+        &self.principal
+    }
+
+    /// 根节点着法取得
+    ///
+    /// 返回上一次检索的根节点所有着法的评价值和主要变例（评价值从高到低）。
+    pub fn root_moves(&self) -> &[RootMove] {
+        &self.root_moves
+    }
+
+    /// 渴望窗口的检索失败取得
+    ///
+    /// 返回上一次限制检索（`search_by_limit`）中渴望窗口的所有失败（按发生的顺序）。
+    pub fn aspiration_fails(&self) -> &[AspirationFail] {
+        &self.aspiration_fails
+    }
+
+    /// 选择性检索取得
+    pub fn selectivity(&self) -> Selectivity {
+        self.selectivity
+    }
+
+    /// 选择性检索设置
+    ///
+    /// * `selectivity` - 选择性检索。
+    pub fn set_selectivity(&mut self, selectivity: Selectivity) {
+        self.selectivity = selectivity;
+    }
+
+    /// 检索线程数设置
+    ///
+    /// * `threads` - 线程数（1~`MAX_SEARCH_THREADS`）。
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.clamp(1usize, MAX_SEARCH_THREADS);
+    }
+
+    /// 置换表大小设置
+    ///
+    /// 与现在的大小不同时放弃现在的置换表，下一次检索时以新的大小分配。
+    ///
+    /// * `size` - 大小（MB，1~`MAX_HASH_SIZE`）。
+    pub fn set_hash_size(&mut self, size: usize) {
+        let size: usize = size.clamp(1usize, MAX_HASH_SIZE);
+        if size != self.hash_size {
+            self.hash_size = size;
+            self.table = None;
+        }
+    }
+
+    /// 置换表设置
+    ///
+    /// 使用外部的置换表（多个局面之间共用），大小为该置换表的大小。
+    ///
+    /// * `table` - 置换表。
+    pub fn set_table(&mut self, table: Arc<TranspositionTable>) {
+        self.hash_size = table.size();
+        self.table = Some(table);
+    }
+
+    /// 置换表的键值
+    ///
+    /// 局面键值加上走棋方。
+    ///
+    /// * `side` - 走棋方。
+    fn table_key(&self, side: &Side) -> u64 {
+        match side {
+            Side::Red => self.key,
+            Side::Black => self.key ^ ZOBRIST_BLACK,
+        }
+    }
+
+    /// 评价噪声
+    ///
+    /// 由局面键值和种子决定的`-eval_noise`~`eval_noise`的值，同一次对局中相同局面的噪声相同。
+    fn noise(&self) -> i32 {
+        if self.eval_noise <= 0i32 {
+            return 0i32;
+        }
+        let mut hash: u64 = self.key ^ self.noise_seed;
+        hash = (hash ^ (hash >> 33)).wrapping_mul(0xFF51_AFD7_ED55_8CCD);
+        hash ^= hash >> 33;
+        (hash % (self.eval_noise as u64 * 2u64 + 1u64)) as i32 - self.eval_noise
+    }
+
+    /// 评价值取得
+    ///
+    /// 返回上一次检索的评价值（走棋方视角，正数为走棋方有利）。
+    pub fn score(&self) -> i32 {
+        self.score
+    }
+
+    /// 检索深度取得
+    ///
+    /// 返回上一次检索完成的深度。
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// 棋子取得
+    ///
+    /// 返回指定位置的棋子。
+    ///
+    /// * `position` - 位置（0~89）。
+    pub fn get_piece(&self, position: usize) -> Option<char> {
+        self.positions[position]
+    }
+
+    /// 走棋方取得
+    ///
+    /// 返回当前走棋方。
+    pub fn side(&self) -> Side {
+        self.side
+    }
+
+    /// FEN取得
+    ///
+    /// 将棋盘转换成FEN字符串（包含走棋方），没有吃子的步数不为0时追加第5个字段。
+    pub fn to_fen(&self) -> String {
+        let mut fen = String::new();
+        let mut space = 0usize;
+        for i in 0usize..MAX_CELLS_SIZE {
+            match self.positions[i] {
+                None => {
+                    space += 1;
+                }
+                Some(p) => {
+                    if space > 0usize {
+                        fen.push_str(&space.to_string());
+                        space = 0usize;
+                    }
+                    fen.push(p);
+                }
+            }
+            if i % WIDTH == 8usize {
+                if space > 0usize {
+                    fen.push_str(&space.to_string());
+                    space = 0usize;
+                }
+                if i != 89usize {
+                    fen.push('/');
+                }
+            }
+        }
+        fen.push(' ');
+        fen.push_str(self.side.to_fen());
+        let plies: usize = self.no_capture_plies();
+        if plies > 0usize {
+            fen.push_str(&format!(" - - {}", plies));
+        }
+        fen
+    }
+
+    /// 着法执行
+    ///
+    /// 执行着法并交换走棋方，返回被吃掉的棋子。
+    ///
+    /// * `from` - 棋子移动开始位置。
+    /// * `to` - 棋子移动对象位置。
+    pub fn make_move(&mut self, from: usize, to: usize) -> Option<char> {
+        self.side = self.side.opponent();
+        let piece: char = self.positions[from].unwrap();
+        self.do_move(&Move::new(piece, from, to))
+    }
+
+    /// 着法撤销
+    ///
+    /// 撤销`make_move`执行的最后一步着法并交换走棋方，没有历史时返回None。
+    pub fn unmake_move(&mut self) -> Option<Move> {
+        let entry: HistoryEntry = *self.history.last()?;
+        self.side = self.side.opponent();
+        self.undo_move(entry.captured);
+        Some(entry.node)
+    }
+
+    /// 着法历史取得
+    pub fn history(&self) -> Vec<Move> {
+        self.history.iter().map(|entry| entry.node).collect()
+    }
+
+    /// 局面键值取得
+    ///
+    /// 返回棋子配置的Zobrist键值（不包含走棋方）。
+    pub fn key(&self) -> u64 {
+        self.key
+    }
+
+    /// 评价参数设置
+    ///
+    /// * `params` - 评价参数。
+    pub fn set_params(&mut self, params: Arc<EvaluateParams>) {
+        self.params = params;
+    }
+
+    /// 重复局面规则设置
+    ///
+    /// * `rule` - 重复局面规则。
+    pub fn set_rule(&mut self, rule: RepetitionRule) {
+        self.rule = rule;
+    }
+
+    /// 没有吃子的步数取得
+    ///
+    /// 从最后一次吃子（没有吃子时从开始局面）到现在的步数。
+    pub fn no_capture_plies(&self) -> usize {
+        match self.history.iter().rev().position(|entry| entry.captured.is_some()) {
+            Some(plies) => plies,
+            None => self.initial_plies + self.history.len(),
+        }
+    }
+
+    /// 自然限着检测
+    ///
+    /// 双方`MOVE_LIMIT_PLIES`步没有吃子时返回true。
+    pub fn is_move_limit(&self) -> bool {
+        self.no_capture_plies() >= MOVE_LIMIT_PLIES
+    }
+
+    /// 子力不足检测
+    ///
+    /// 双方都只剩帅（将）、仕（士）、相（象），无法将死对方时返回true。
+    pub fn is_insufficient_material(&self) -> bool {
+        !self
+            .positions
+            .iter()
+            .any(|p| p.is_some_and(|c| ATTACKING_PIECES.contains(c)))
+    }
+
+    /// 重复局面判定
+    ///
+    /// 当前局面与之前同一走棋方的局面重复时，按规则判定循环中双方的着法，没有重复时返回None。
+    /// 检索中使用，第一次重复就判定。
+    pub fn repetition(&mut self) -> Option<RepetitionResult> {
+        let cycle: usize = self.repetition_cycle(2usize)?;
+        Some(self.adjudicate(cycle))
+    }
+
+    /// 对局的重复局面判定
+    ///
+    /// 当前局面（同一走棋方）第`GAME_REPETITION_COUNT`次出现时，按规则判定从第一次出现开始的所有着法，
+    /// 次数不足时返回None。
+    pub fn game_repetition(&mut self) -> Option<RepetitionResult> {
+        let cycle: usize = self.repetition_cycle(GAME_REPETITION_COUNT)?;
+        Some(self.adjudicate(cycle))
+    }
+
+    /// 合法着法生成
+    ///
+    /// 生成走棋方所有合法着法（过滤走棋后被将军或对将的着法）。
+    ///
+    /// * `side` - 红色或者黑色，当前棋子的移动方。
+    pub fn generate_legal_moves(&mut self, side: &Side) -> Vec<Move> {
+        let mut all_moves: Vec<Move> = self.generate_all_moves(side);
+        all_moves.retain(|node| {
+            let position_to_backup: Option<char> = self.temporary_move(node.from, node.to);
+            let checked: bool = self.is_checked(side);
+            self.recovery(node.from, node.to, position_to_backup);
+            !checked
+        });
+        all_moves
+    }
+
+    /// 将军检测
+    ///
+    /// 检测指定方的帅（将）是否被攻击（包含对将），帅（将）不存在时也视为被将军。
+    ///
+    /// * `side` - 红色或者黑色，检测对象方。
+    pub fn is_checked(&mut self, side: &Side) -> bool {
+        let king_position: usize = self.get_king_position(side);
+        let (king, rook, cannon, knight, pawn, opponent_king) = match side {
+            Side::Red => (RED_KING, BLACK_ROOK, BLACK_CANNON, BLACK_KNIGHT, BLACK_PAWN, BLACK_KING),
+            Side::Black => (BLACK_KING, RED_ROOK, RED_CANNON, RED_KNIGHT, RED_PAWN, RED_KING),
+        };
+        if self.positions[king_position] != Some(king) {
+            return true;
+        }
+
+        // 车、对将
+        let king_column: usize = INDEX_COLUMN[king_position];
+        for position in self.generate_piece_move_by_four_direction(king_position, 0usize) {
+            match self.positions[position] {
+                Some(p) if p == rook => return true,
+                Some(p) if p == opponent_king && INDEX_COLUMN[position] == king_column => {
+                    return true
+                }
+                _ => {}
+            }
+        }
+
+        // 炮
+        for position in self.generate_piece_move_by_four_direction(king_position, 1usize) {
+            if self.positions[position] == Some(cannon) {
+                return true;
+            }
+        }
+
+        let row: i32 = INDEX_ROW[king_position] as i32;
+        let column: i32 = INDEX_COLUMN[king_position] as i32;
+
+        // 马（马腿为帅（将）斜向相邻的位置）
+        for &(row_offset, column_offset) in &[
+            (-2, -1), (-2, 1), (2, -1), (2, 1), (-1, -2), (1, -2), (-1, 2), (1, 2),
+        ] {
+            let knight_position = self.get_position(row + row_offset, column + column_offset);
+            let leg_position = self.get_position(row + row_offset.signum(), column + column_offset.signum());
+            if let (Some(k), Some(l)) = (knight_position, leg_position) {
+                if self.positions[k] == Some(knight) && self.is_empty(l) {
+                    return true;
+                }
+            }
+        }
+
+        // 兵（卒），帅（将）在九宫内，对方的兵（卒）必定已经过河
+        let forward: i32 = match side {
+            Side::Red => -1,
+            Side::Black => 1,
+        };
+        for &(row_offset, column_offset) in &[(forward, 0), (0, -1), (0, 1)] {
+            if let Some(p) = self.get_position(row + row_offset, column + column_offset) {
+                if self.positions[p] == Some(pawn) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// 走法数统计
+    ///
+    /// 统计指定深度的合法着法叶子节点数，用于验证着法生成。
+    ///
+    /// * `depth` - 深度。
+    pub fn perft(&mut self, depth: usize) -> u64 {
+        if depth == 0usize {
+            return 1u64;
+        }
+        let side: Side = self.side;
+        let all_moves: Vec<Move> = self.generate_legal_moves(&side);
+        if depth == 1usize {
+            return all_moves.len() as u64;
+        }
+        let mut nodes = 0u64;
+        for node in all_moves {
+            self.make_move(node.from, node.to);
+            nodes += self.perft(depth - 1usize);
+            self.unmake_move();
+        }
+        nodes
+    }
+
+    /// 节点数取得
+    ///
+    /// 返回上一次检索访问的节点数（包含根节点）。
+    pub fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    /// ICCS坐标解析
+    ///
+    /// 将ICCS格式的着法（例：h2e2）解析成【开始位置, 结束位置】，格式错误时返回None。
+    ///
+    /// * `iccs` - ICCS格式的着法。
+    pub fn parse_iccs(&self, iccs: &str) -> Option<(usize, usize)> {
+        let bytes: Vec<u8> = iccs.trim().to_ascii_lowercase().into_bytes();
+        if bytes.len() != 4usize {
+            return None;
+        }
+        let mut positions: [usize; 2] = [0usize; 2];
+        for (i, chunk) in bytes.chunks(2).enumerate() {
+            if !(b'a'..=b'i').contains(&chunk[0]) || !chunk[1].is_ascii_digit() {
+                return None;
+            }
+            let column: usize = (chunk[0] - b'a') as usize;
+            let row: usize = (b'9' - chunk[1]) as usize;
+            positions[i] = INDEX_ROW_POSITIONS[row][column];
+        }
+        Some((positions[0], positions[1]))
+    }
+
+    /// 极大极小值计算
+    ///
+    /// 返回计算后的极大极小值。
+    ///
+    /// * `depth` - 深度。
+    /// * `min` - 极小值。
+    /// * `max` - 极大值。
+    /// * `side` - 红色或者黑色，当前棋子的移动方。
+    fn min_max(&mut self, depth: usize, min: i32, max: i32, side: &Side) -> i32 {
+        self.nodes += 1u64;
+        if self.main_thread && self.nodes & LIMIT_CHECK_MASK == 0u64 {
+            self.check_limits();
+        }
+        // 该局面之后的主要变例（评价值改善时更新）
+        let ply: usize = self.history.len() - self.root_ply;
+        self.pv[ply].clear();
+        // 检索被停止时结果不再使用
+        if self.stop.load(Ordering::Relaxed) {
+            return 0i32;
+        }
+        // 重复局面按规则判定
+        if let Some(result) = self.repetition() {
+            return match result {
+                RepetitionResult::Draw => 0i32,
+                RepetitionResult::Loss(Side::Red, _) => REPETITION_LOSS_VALUE,
+                RepetitionResult::Loss(Side::Black, _) => -REPETITION_LOSS_VALUE,
+            };
+        }
+        // 自然限着以及子力不足（只在吃子后检测）判和
+        if self.is_move_limit() {
+            return 0i32;
+        }
+        if self.history.last().is_some_and(|entry| entry.captured.is_some())
+            && self.is_insufficient_material()
+        {
+            return 0i32;
+        }
+        // 将军延伸：被将军时多检索一步，延伸在本次检索深度的2倍以及最大深度以内
+        let selective: bool = self.selectivity != Selectivity::none();
+        let in_check: bool = selective && (depth > 0usize || self.selectivity.extension) && self.is_checked(side);
+        let extend: bool = self.selectivity.extension
+            && in_check
+            && ply < self.depth * 2usize
+            && ply + depth + 2usize <= MAX_SEARCH_DEPTH;
+        let depth: usize = if extend { depth + 1usize } else { depth };
+        match depth {
+            0 => self.evaluate() + self.noise(),
+            _ => {
+                // 将死距离剪枝：比当前步数更快的将死不可能出现
+                let ply: i32 = ply as i32;
+                let (lower, upper) = match side {
+                    Side::Black => (-(MATE_VALUE - ply), MATE_VALUE - ply - 1),
+                    Side::Red => (-(MATE_VALUE - ply - 1), MATE_VALUE - ply),
+                };
+                let mut min_copy: i32 = cmp::min(min, upper);
+                let mut max_copy: i32 = cmp::max(max, lower);
+                if min_copy <= max_copy {
+                    return match side {
+                        Side::Red => min_copy,
+                        Side::Black => max_copy,
+                    };
+                }
+
+                // 置换表：深度足够时直接使用记录的评价值，否则最先检索记录的最佳着法
+                let table_key: u64 = self.table_key(side);
+                let mut table_move: Option<(usize, usize)> = None;
+                if let Some(entry) = self.table.as_ref().and_then(|table| table.probe(table_key)) {
+                    if entry.depth >= depth {
+                        let value: i32 = value_from_table(entry.value, ply);
+                        match entry.bound {
+                            Bound::Exact => return value,
+                            Bound::Lower if value >= min_copy => return value,
+                            Bound::Upper if value <= max_copy => return value,
+                            _ => {}
+                        }
+                    }
+                    table_move = entry.best;
+                }
+
+                let opponent: Side = side.opponent();
+                // 剪枝比较的窗口（黑方为上限，红方为下限）和被剪枝一方的窗口为将死时不剪枝
+                let (cut_bound, fail_bound) = match side {
+                    Side::Black => (min_copy, max_copy),
+                    Side::Red => (max_copy, min_copy),
+                };
+                let null_move: bool = self.selectivity.null_move
+                    && depth >= NULL_MOVE_MIN_DEPTH
+                    && !in_check
+                    && cut_bound.abs() < MATE_BOUND
+                    && !self.history.last().is_some_and(|entry| entry.node.is_null())
+                    && self.has_major_pieces(side);
+                let futility: bool = self.selectivity.futility
+                    && depth < FUTILITY_MARGINS.len()
+                    && !in_check
+                    && fail_bound.abs() < MATE_BOUND;
+                let static_value: i32 = if null_move || futility { self.evaluate() + self.noise() } else { 0i32 };
+
+                // 空着剪枝：让对方连走两步（深度减少）仍然超过窗口时剪枝
+                if null_move {
+                    let null_depth: usize = depth - 1usize - NULL_MOVE_REDUCTION;
+                    match side {
+                        Side::Black if static_value >= min_copy => {
+                            self.do_null_move();
+                            let value: i32 = self.min_max(null_depth, min_copy, min_copy - 1i32, &opponent);
+                            self.undo_null_move();
+                            if value >= min_copy && !self.stop.load(Ordering::Relaxed) {
+                                return min_copy;
+                            }
+                        }
+                        Side::Red if static_value <= max_copy => {
+                            self.do_null_move();
+                            let value: i32 = self.min_max(null_depth, max_copy + 1i32, max_copy, &opponent);
+                            self.undo_null_move();
+                            if value <= max_copy && !self.stop.load(Ordering::Relaxed) {
+                                return max_copy;
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+
+                let (lower, upper) = (max_copy, min_copy);
+                let mut best: Option<(usize, usize)> = None;
+
+                let mut legal_moves = 0usize;
+                let mut all_moves: Vec<Move> = if in_check {
+                    self.generate_legal_moves(side)
+                } else {
+                    self.generate_all_moves(side)
+                };
+                // 唯一应着延伸：被将军且只有一个应着时再多检索一步
+                let depth: usize = if extend && all_moves.len() == 1usize { depth + 1usize } else { depth };
+                if let Some((from, to)) = table_move {
+                    if let Some(index) = all_moves.iter().position(|m| m.from == from && m.to == to) {
+                        // 着法从末尾取出
+                        let last: usize = all_moves.len() - 1usize;
+                        all_moves.swap(index, last);
+                    }
+                }
+                while let Some(node) = all_moves.pop() {
+                    let position_to_backup: Option<char> = self.do_move(&node);
+                    // 被将军的着法不合法
+                    if self.is_checked(side) {
+                        self.undo_move(position_to_backup);
+                        continue;
+                    }
+                    legal_moves += 1usize;
+                    // 不吃子、不将军的着法（置换表的着法除外）为剪枝和减少深度的对象
+                    let late: bool =
+                        self.selectivity.late_move_reduction && depth >= LMR_MIN_DEPTH && legal_moves > LMR_FULL_MOVES;
+                    let quiet: bool = (futility || late)
+                        && position_to_backup.is_none()
+                        && table_move != Some((node.from, node.to))
+                        && !self.is_checked(&opponent);
+                    // 无益剪枝：静态评价加上余量也达不到窗口时不检索
+                    if futility && quiet {
+                        let margin: i32 = FUTILITY_MARGINS[depth];
+                        let hopeless: bool = match side {
+                            Side::Black => static_value + margin <= max_copy,
+                            Side::Red => static_value - margin >= min_copy,
+                        };
+                        if hopeless {
+                            self.undo_move(position_to_backup);
+                            continue;
+                        }
+                    }
+                    // 主要变例检索：第二个以后的着法以零窗口检索，改善窗口时再以完整的窗口重新检索
+                    let pvs: bool = self.selectivity.pvs && legal_moves > 1usize;
+                    let (null_min, null_max) = match side {
+                        Side::Black => (max_copy + 1i32, max_copy),
+                        Side::Red => (min_copy, min_copy - 1i32),
+                    };
+                    let improved = |value: i32| match side {
+                        Side::Black => value > max_copy,
+                        Side::Red => value < min_copy,
+                    };
+                    let cutoff = |value: i32| match side {
+                        Side::Black => value >= min_copy,
+                        Side::Red => value <= max_copy,
+                    };
+                    // 后期着法减少深度：超过窗口时以完整的深度重新检索
+                    let mut value: Option<i32> = None;
+                    if late && quiet && !in_check {
+                        let reduced: i32 = if pvs {
+                            self.min_max(depth - 2usize, null_min, null_max, &opponent)
+                        } else {
+                            self.min_max(depth - 2usize, min_copy, max_copy, &opponent)
+                        };
+                        if !improved(reduced) {
+                            value = Some(reduced);
+                        }
+                    }
+                    if value.is_none() && pvs {
+                        let scout: i32 = self.min_max(depth - 1usize, null_min, null_max, &opponent);
+                        if !improved(scout) || cutoff(scout) {
+                            value = Some(scout);
+                        }
+                    }
+                    let value: i32 = match value {
+                        Some(value) => value,
+                        None => self.min_max(depth - 1usize, min_copy, max_copy, &opponent),
+                    };
+                    match side {
+                        Side::Red => {
+                            if value < min_copy {
+                                min_copy = value;
+                                best = Some((node.from, node.to));
+                                self.update_pv(ply as usize, node);
+                            }
+                        }
+                        Side::Black => {
+                            if value > max_copy {
+                                max_copy = value;
+                                best = Some((node.from, node.to));
+                                self.update_pv(ply as usize, node);
+                            }
+                        }
+                    }
+                    self.undo_move(position_to_backup);
+                    if min_copy <= max_copy {
+                        break;
+                    }
+                }
+                if self.stop.load(Ordering::Relaxed) {
+                    return 0i32;
+                }
+                // 无合法着法（将死或者困毙）时走棋方判负，步数越少评价值越大
+                if legal_moves == 0usize {
+                    return match side {
+                        Side::Red => MATE_VALUE - ply,
+                        Side::Black => -(MATE_VALUE - ply),
+                    };
+                }
+                let value: i32 = match side {
+                    Side::Red => min_copy,
+                    Side::Black => max_copy,
+                };
+                let bound: Bound = if value <= lower {
+                    Bound::Upper
+                } else if value >= upper {
+                    Bound::Lower
+                } else {
+                    Bound::Exact
+                };
+                if let Some(table) = &self.table {
+                    table.store(
+                        table_key,
+                        TtEntry {
+                            depth,
+                            value: value_to_table(value, ply),
+                            bound,
+                            best,
+                        },
+                    );
+                }
+                value
+            }
+        }
+    }
+
+    /// 棋子评价
+    ///
+    /// 对棋盘上所有棋子评价（黑方视角）。
+    /// 中局和残局分别计算棋子价值和位置分（中局另外计算帅（将）的安全性），
+    /// 按剩余子力的阶段值混合，再加上棋子的活动性。
+    pub fn evaluate(&self) -> i32 {
+        let params: &EvaluateParams = &self.params;
+        let mut middlegame = 0i32;
+        let mut endgame = 0i32;
+        let mut phase = 0i32;
+        let mut activity = 0i32;
+        for i in 0..MAX_CELLS_SIZE {
+            if let Some(p) = self.positions[i] {
+                let terms: PieceTrace = self.piece_terms(i, p);
+                let sign: i32 = if p.is_ascii_uppercase() { -1i32 } else { 1i32 };
+                middlegame += sign * (terms.material[0] + terms.position[0]);
+                endgame += sign * (terms.material[1] + terms.position[1]);
+                activity += sign * terms.activity();
+                phase += params.phase[piece_kind(p)];
+            }
+        }
+        // 帅（将）的安全性只在中局评价
+        middlegame += self.king_safety(Side::Black) - self.king_safety(Side::Red);
+        let total: i32 = params.total_phase();
+        let phase: i32 = cmp::min(phase, total);
+        (middlegame * phase + endgame * (total - phase)) / total + activity
+    }
+
+    /// 评价明细
+    ///
+    /// 返回`evaluate`的各项明细（双方、每个棋子），调试和调整评价参数用。
+    pub fn evaluate_trace(&self) -> EvaluateTrace {
+        let params: &EvaluateParams = &self.params;
+        let mut red: SideTrace = SideTrace::new(Side::Red, self.king_safety_terms(Side::Red));
+        let mut black: SideTrace = SideTrace::new(Side::Black, self.king_safety_terms(Side::Black));
+        let mut phase = 0i32;
+        for i in 0..MAX_CELLS_SIZE {
+            if let Some(p) = self.positions[i] {
+                let terms: PieceTrace = self.piece_terms(i, p);
+                if p.is_ascii_uppercase() {
+                    red.pieces.push(terms);
+                } else {
+                    black.pieces.push(terms);
+                }
+                phase += params.phase[piece_kind(p)];
+            }
+        }
+        let total_phase: i32 = params.total_phase();
+        EvaluateTrace {
+            red,
+            black,
+            phase: cmp::min(phase, total_phase),
+            total_phase,
+            score: self.evaluate(),
+        }
+    }
+
+    /// 棋子的评价项目
+    ///
+    /// 计算一个棋子的棋子价值、位置分（中局、残局）以及活动性（该棋子一方视角）。
+    ///
+    /// * `position` - 棋子位置。
+    /// * `piece` - 棋子。
+    fn piece_terms(&self, position: usize, piece: char) -> PieceTrace {
+        let params: &EvaluateParams = &self.params;
+        let kind: usize = piece_kind(piece);
+        // 位置分表为红方视角，黑方使用旋转后的位置
+        let square: usize = if piece.is_ascii_uppercase() { position } else { 89 - position };
+        let (mobility, cannon_platform, rook_file) = self.activity(position, piece);
+        PieceTrace {
+            piece,
+            square: position,
+            material: [params.middlegame.material[kind], params.endgame.material[kind]],
+            position: [
+                params.middlegame.table(kind)[square] * params.position_weight,
+                params.endgame.table(kind)[square] * params.position_weight,
+            ],
+            mobility,
+            cannon_platform,
+            rook_file,
+        }
+    }
+
+    /// 帅（将）的安全性评价
     ///
-    /// 将移动位置转换成4位字符串，分别代表【开始.x, 开始.y, 结束.x, 结束.y】，坐标值从0开始。
+    /// 返回指定方视角的评价值（`king_safety_terms`的合计）。
     ///
-    /// * `from` - 开始位置。
-    /// * `to` - 移动对象位置。
-    fn translate(&self, from: usize, to: usize) -> String {
-        let mut position: String = String::new();
-        position.push_str(&INDEX_ROW[from].to_string());
-        position.push_str(&INDEX_COLUMN[from].to_string());
-        position.push_str(&INDEX_ROW[to].to_string());
-        position.push_str(&INDEX_COLUMN[to].to_string());
-        position
+    /// * `side` - 帅（将）的一方。
+    fn king_safety(&self, side: Side) -> i32 {
+        self.king_safety_terms(side).iter().sum()
     }
 
-    /// 极大极小值计算
+    /// 帅（将）的安全性评价项目
     ///
-    /// 返回计算后的极大极小值。
+    /// 统计九宫周围的进攻方和防守方的棋子，缺少仕（士）相（象）、对方的车或炮在帅（将）所在的列、
+    /// 对方的马在九宫附近以及帅（将）前方没有己方的棋子时扣分。
+    /// 返回指定方视角的各项评价值（顺序与`KING_SAFETY_TERMS`相同）。
     ///
-    /// * `depth` - 深度。
-    /// * `min` - 极小值。
-    /// * `max` - 极大值。
-    /// * `side` - 红色或者黑色，当前棋子的移动方。
-    fn min_max(&mut self, depth: usize, min: i32, max: i32, side: &Side) -> i32 {
-        match depth {
-            0 => {
-                // Or any one of kings has been killed?
-                self.evaluate()
+    /// * `side` - 帅（将）的一方。
+    fn king_safety_terms(&self, side: Side) -> [i32; 8] {
+        let safety: &KingSafetyParams = &self.params.king_safety;
+        let (palace, king, is_red) = match side {
+            Side::Red => (&RED_KING_POSITIONS, RED_KING, true),
+            Side::Black => (&BLACK_KING_POSITIONS, BLACK_KING, false),
+        };
+        let king_position: usize = match palace.iter().find(|p| self.positions[**p] == Some(king)) {
+            Some(p) => *p,
+            None => return [0i32; 8],
+        };
+        let king_column: usize = INDEX_COLUMN[king_position];
+        // 离己方底线的行数
+        let distance = |position: usize| -> usize {
+            if is_red {
+                HEIGHT - 1usize - INDEX_ROW[position]
+            } else {
+                INDEX_ROW[position]
             }
-            _ => {
-                let mut min_copy: i32 = min;
-                let mut max_copy: i32 = max;
-                let mut all_moves: Vec<MinMaxNode> = self.generate_all_moves(&side);
-                while let Some(node) = all_moves.pop() {
-                    let position_to_backup: Option<char> = self.temporary_move(node.from, node.to);
-                    match side {
-                        Side::Red => {
-                            min_copy = cmp::min(
-                                min_copy,
-                                self.min_max(depth - 1, min_copy, max_copy, &Side::Black),
-                            );
-                        }
-                        Side::Black => {
-                            max_copy = cmp::max(
-                                max_copy,
-                                self.min_max(depth - 1, min_copy, max_copy, &Side::Red),
-                            );
-                        }
-                    }
-                    self.recovery(node.from, node.to, position_to_backup);
-                    if min <= max {
-                        break;
-                    }
+        };
+
+        // missing_adviser, missing_bishop, rook_on_king_file, central_cannon,
+        // knight_near_palace, palace_attacker, palace_defender, king_open_file
+        let mut terms = [0i32; 8];
+        let mut advisers = 0i32;
+        let mut bishops = 0i32;
+        let mut shielded = false;
+        for (i, square) in self.positions.iter().enumerate() {
+            let piece: char = match square {
+                Some(p) => *p,
+                None => continue,
+            };
+            let column: usize = INDEX_COLUMN[i];
+            if piece.is_ascii_uppercase() == is_red {
+                match piece.to_ascii_uppercase() {
+                    RED_KING => continue,
+                    RED_ADVISER => advisers += 1i32,
+                    RED_BISHOP => bishops += 1i32,
+                    _ => {}
                 }
-                match side {
-                    Side::Red => min_copy,
-                    Side::Black => max_copy,
+                if palace.contains(&i) {
+                    terms[6] += safety.palace_defender;
+                }
+                if column == king_column && distance(i) > distance(king_position) {
+                    shielded = true;
+                }
+                continue;
+            }
+            let near_palace: bool = (3usize..=5usize).contains(&column) && distance(i) <= 3usize;
+            match piece.to_ascii_uppercase() {
+                RED_ROOK | RED_CANNON | RED_PAWN if near_palace => terms[5] -= safety.palace_attacker,
+                _ => {}
+            }
+            match piece.to_ascii_uppercase() {
+                RED_ROOK if column == king_column => terms[2] -= safety.rook_on_king_file,
+                RED_CANNON if column == king_column => terms[3] -= safety.central_cannon,
+                RED_KNIGHT if (2usize..=6usize).contains(&column) && distance(i) <= 4usize => {
+                    terms[4] -= safety.knight_near_palace
                 }
+                _ => {}
             }
         }
+        terms[0] -= cmp::max(2i32 - advisers, 0i32) * safety.missing_adviser;
+        terms[1] -= cmp::max(2i32 - bishops, 0i32) * safety.missing_bishop;
+        if !shielded {
+            terms[7] -= safety.king_open_file;
+        }
+        terms
     }
 
-    /// 棋子评价
+    /// 棋子活动性评价
     ///
-    /// 对棋盘上所有棋子评价。
-    fn evaluate(&self) -> i32 {
-        // By default, only calculate black's value
-        let mut sum_red = 0i32;
-        let mut sum_black = 0i32;
-        for i in 0..MAX_CELLS_SIZE {
-            if let Some(p) = self.positions[i] {
-                match p {
-                    RED_KING => {
-                        sum_red += EVALUATE_BASIC[0];
-//                        sum_red += EVALUATE_KING[i];
-                    }
-                    RED_ADVISER => {
-                        sum_red += EVALUATE_BASIC[1];
-//                        sum_red += EVALUATE_ADVISER[i];
-                    }
-                    RED_BISHOP => {
-                        sum_red += EVALUATE_BASIC[2];
-//                        sum_red += EVALUATE_BISHOP[i];
-                    }
-                    RED_KNIGHT => {
-                        sum_red += EVALUATE_BASIC[3];
-                        sum_red += EVALUATE_KNIGHT[i] * 8;
-                    }
-                    RED_ROOK => {
-                        sum_red += EVALUATE_BASIC[4];
-                        sum_red += EVALUATE_ROOK[i] * 8;
-                    }
-                    RED_CANNON => {
-                        sum_red += EVALUATE_BASIC[5];
-                        sum_red += EVALUATE_CANNON[i] * 8;
-                    }
-                    RED_PAWN => {
-                        sum_red += EVALUATE_BASIC[6];
-                        sum_red += EVALUATE_PAWN[i] * 8;
-                    }
-                    BLACK_KING => {
-                        sum_black += EVALUATE_BASIC[0];
-//                        sum_black += EVALUATE_KING[89 - i];
-                    }
-                    BLACK_ADVISER => {
-                        sum_black += EVALUATE_BASIC[1];
-//                        sum_black += EVALUATE_ADVISER[89 - i];
-                    }
-                    BLACK_BISHOP => {
-                        sum_black += EVALUATE_BASIC[2];
-//                        sum_black += EVALUATE_BISHOP[89 - i];
-                    }
-                    BLACK_KNIGHT => {
-                        sum_black += EVALUATE_BASIC[3];
-                        sum_black += EVALUATE_KNIGHT[89 - i] * 8;
-                    }
-                    BLACK_ROOK => {
-                        sum_black += EVALUATE_BASIC[4];
-                        sum_black += EVALUATE_ROOK[89 - i] * 8;
-                    }
-                    BLACK_CANNON => {
-                        sum_black += EVALUATE_BASIC[5];
-                        sum_black += EVALUATE_CANNON[89 - i] * 8;
-                    }
-                    BLACK_PAWN => {
-                        sum_black += EVALUATE_BASIC[6];
-                        sum_black += EVALUATE_PAWN[89 - i] * 8;
-                    }
-                    _ => {}
+    /// 马、车、炮的机动性（可以到达的空位以及对方棋子的个数，马腿有棋子时不能到达）、
+    /// 炮架（隔一个棋子可以吃掉的对方棋子）以及车所在列的兵（卒）的情况。
+    /// 返回机动性、炮架、车所在列的评价值。
+    ///
+    /// * `position` - 棋子位置。
+    /// * `piece` - 棋子。
+    fn activity(&self, position: usize, piece: char) -> (i32, i32, i32) {
+        let params: &EvaluateParams = &self.params;
+        let kind: usize = piece_kind(piece);
+        let (mobility, captures) = match piece.to_ascii_uppercase() {
+            RED_KNIGHT => self.count_targets(piece, &self.knight_targets(position)),
+            RED_ROOK => self.count_line_targets(piece, position, 0usize),
+            RED_CANNON => self.count_line_targets(piece, position, 1usize),
+            _ => return (0i32, 0i32, 0i32),
+        };
+        let mut cannon_platform = 0i32;
+        let mut rook_file = 0i32;
+
+        match piece.to_ascii_uppercase() {
+            // 炮的移动位置中的棋子为炮架后面的棋子
+            RED_CANNON => cannon_platform = captures * params.cannon_platform,
+            RED_ROOK => {
+                let (own_pawn, enemy_pawn) = if piece.is_ascii_uppercase() {
+                    (RED_PAWN, BLACK_PAWN)
+                } else {
+                    (BLACK_PAWN, RED_PAWN)
+                };
+                let file = &INDEX_COLUMN_POSITIONS[INDEX_COLUMN[position]];
+                let has_pawn = |pawn: char| file.iter().any(|p| self.positions[*p] == Some(pawn));
+                if !has_pawn(own_pawn) {
+                    rook_file = if has_pawn(enemy_pawn) {
+                        params.rook_half_open_file
+                    } else {
+                        params.rook_open_file
+                    };
                 }
             }
+            _ => {}
         }
-        sum_black - sum_red
+        (mobility * params.mobility[kind], cannon_platform, rook_file)
     }
 
     /// 移动棋子
@@ -473,6 +1984,11 @@ impl Board {
     /// 返回棋子移动对象位置的棋子备份
     fn temporary_move(&mut self, from: usize, to: usize) -> Option<char> {
         let piece = self.positions[to];
+        let moving: char = self.positions[from].unwrap();
+        self.key ^= zobrist(moving, from) ^ zobrist(moving, to);
+        if let Some(p) = piece {
+            self.key ^= zobrist(p, to);
+        }
         self.positions[to] = self.positions[from];
         self.positions[from] = None;
         if piece.is_some() {
@@ -482,6 +1998,11 @@ impl Board {
     }
 
     fn recovery(&mut self, from: usize, to: usize, position_to_backup: Option<char>) {
+        let moving: char = self.positions[to].unwrap();
+        self.key ^= zobrist(moving, from) ^ zobrist(moving, to);
+        if let Some(p) = position_to_backup {
+            self.key ^= zobrist(p, to);
+        }
         self.positions[from] = self.positions[to];
         self.positions[to] = position_to_backup;
         if position_to_backup.is_some() {
@@ -489,11 +2010,196 @@ impl Board {
         }
     }
 
+    /// 着法执行（记录历史）
+    ///
+    /// 移动棋子并记录着法历史，返回被吃掉的棋子。
+    ///
+    /// * `node` - 着法。
+    fn do_move(&mut self, node: &Move) -> Option<char> {
+        let key: u64 = self.key;
+        let captured: Option<char> = self.temporary_move(node.from, node.to);
+        self.history.push(HistoryEntry {
+            key,
+            node: *node,
+            captured,
+        });
+        captured
+    }
+
+    /// 空着执行（记录历史）
+    ///
+    /// 不移动棋子，只在历史中记录空着（步数增加，重复局面检测到此为止）。
+    fn do_null_move(&mut self) {
+        self.history.push(HistoryEntry {
+            key: self.key,
+            node: Move::null(),
+            captured: None,
+        });
+    }
+
+    /// 空着撤销
+    fn undo_null_move(&mut self) {
+        self.history.pop();
+    }
+
+    /// 车马炮检测
+    ///
+    /// 指定方有车、马或者炮时返回true（没有时容易出现等着，不使用空着剪枝）。
+    ///
+    /// * `side` - 红色或者黑色。
+    fn has_major_pieces(&self, side: &Side) -> bool {
+        let pieces: [char; 3] = match side {
+            Side::Red => [RED_ROOK, RED_KNIGHT, RED_CANNON],
+            Side::Black => [BLACK_ROOK, BLACK_KNIGHT, BLACK_CANNON],
+        };
+        self.positions.iter().any(|p| p.is_some_and(|c| pieces.contains(&c)))
+    }
+
+    /// 着法撤销（记录历史）
+    ///
+    /// 撤销最后一步`do_move`。
+    ///
+    /// * `captured` - 被吃掉的棋子。
+    fn undo_move(&mut self, captured: Option<char>) {
+        let entry: HistoryEntry = self.history.pop().unwrap();
+        self.recovery(entry.node.from, entry.node.to, captured);
+    }
+
+    /// 重复局面检测
+    ///
+    /// 从最后一步向前查找与当前局面相同、同一走棋方的局面，包含当前局面出现`occurrences`次时，
+    /// 返回到最早一次之间的着法数。遇到吃子或者兵（卒）前进等不可逆着法时停止查找。
+    ///
+    /// * `occurrences` - 出现次数（2以上）。
+    fn repetition_cycle(&self, occurrences: usize) -> Option<usize> {
+        let mut distance = 0usize;
+        let mut found = 1usize;
+        for entry in self.history.iter().rev() {
+            if entry.node.is_null() || entry.captured.is_some() || is_pawn_advance(&entry.node) {
+                return None;
+            }
+            distance += 1usize;
+            if distance.is_multiple_of(2usize) && entry.key == self.key {
+                found += 1usize;
+                if found >= occurrences {
+                    return Some(distance);
+                }
+            }
+        }
+        None
+    }
+
+    /// 重复局面裁定
+    ///
+    /// 重新执行循环中的着法，将双方的着法分类为长将、长捉或者闲着后裁定。
+    /// 违例种类高的一方判负（长将 > 长捉 > 闲着），相同时判和。
+    ///
+    /// * `cycle` - 循环的着法数。
+    fn adjudicate(&mut self, cycle: usize) -> RepetitionResult {
+        let entries: Vec<HistoryEntry> = self.history[self.history.len() - cycle..].to_vec();
+        for entry in entries.iter().rev() {
+            self.recovery(entry.node.from, entry.node.to, entry.captured);
+        }
+        let mut red_kind = RepetitionKind::Check;
+        let mut black_kind = RepetitionKind::Check;
+        for entry in &entries {
+            self.temporary_move(entry.node.from, entry.node.to);
+            let kind: RepetitionKind = self.classify(&entry.node);
+            if entry.node.piece.is_ascii_uppercase() {
+                red_kind = min_kind(red_kind, kind);
+            } else {
+                black_kind = min_kind(black_kind, kind);
+            }
+        }
+
+        if red_kind == black_kind {
+            RepetitionResult::Draw
+        } else if red_kind > black_kind {
+            RepetitionResult::Loss(Side::Red, red_kind)
+        } else {
+            RepetitionResult::Loss(Side::Black, black_kind)
+        }
+    }
+
+    /// 着法分类
+    ///
+    /// 对刚执行的着法分类：将军为长将，捉子为长捉，其他为闲着。
+    /// 捉子指这一步之后新产生的捉（包括移动棋子后露出的车、炮的攻击），着法之前已经被捉的棋子不算。
+    /// 重复循环中的着法不吃子，所以撤销时不需要恢复被吃的棋子。
+    ///
+    /// * `node` - 刚执行的着法。
+    fn classify(&mut self, node: &Move) -> RepetitionKind {
+        let side: Side = if node.piece.is_ascii_uppercase() { Side::Red } else { Side::Black };
+        if self.is_checked(&side.opponent()) {
+            return RepetitionKind::Check;
+        }
+        let after: Vec<usize> = self.chased_targets(&side);
+        self.recovery(node.from, node.to, None);
+        let before: Vec<usize> = self.chased_targets(&side);
+        self.temporary_move(node.from, node.to);
+        if after.iter().any(|target| !before.contains(target)) {
+            RepetitionKind::Chase
+        } else {
+            RepetitionKind::Idle
+        }
+    }
+
+    /// 被捉的棋子取得
+    ///
+    /// `side`一方的棋子（帅（将）、兵（卒）除外）可以吃掉的对方棋子中，无保护的棋子以及被马、炮攻击的车的位置。
+    ///
+    /// * `side` - 捉子的一方。
+    fn chased_targets(&mut self, side: &Side) -> Vec<usize> {
+        let opponent: Side = side.opponent();
+        let captures: Vec<Move> = self
+            .generate_all_moves(side)
+            .into_iter()
+            .filter(|m| self.positions[m.to].is_some())
+            .collect();
+        let mut targets: Vec<usize> = Vec::new();
+        for m in captures {
+            let attacker: char = m.piece.to_ascii_uppercase();
+            let piece: char = self.positions[m.to].unwrap();
+            if attacker == RED_KING || attacker == RED_PAWN || targets.contains(&m.to) || !self.is_chase_target(piece, m.to) {
+                continue;
+            }
+            // 吃子后被将军则不算捉；吃子后对方可以吃回则为有保护
+            let captured: Option<char> = self.temporary_move(m.from, m.to);
+            let legal: bool = !self.is_checked(side);
+            let protected: bool = self
+                .generate_legal_moves(&opponent)
+                .iter()
+                .any(|r| r.to == m.to);
+            self.recovery(m.from, m.to, captured);
+            let rook_attacked: bool = piece.to_ascii_uppercase() == RED_ROOK
+                && (attacker == RED_KNIGHT || attacker == RED_CANNON);
+            if legal && (!protected || rook_attacked) {
+                targets.push(m.to);
+            }
+        }
+        targets
+    }
+
+    /// 被捉对象检测
+    ///
+    /// 帅（将）以及未过河的兵（卒）不算被捉；中国规则中过河的兵（卒）也不算被捉。
+    ///
+    /// * `piece` - 对象棋子。
+    /// * `position` - 对象棋子位置。
+    fn is_chase_target(&self, piece: char, position: usize) -> bool {
+        match piece {
+            RED_KING | BLACK_KING => false,
+            RED_PAWN => INDEX_ROW[position] < 5usize && self.rule == RepetitionRule::Asian,
+            BLACK_PAWN => INDEX_ROW[position] > 4usize && self.rule == RepetitionRule::Asian,
+            _ => true,
+        }
+    }
+
     /// 所有棋子可能移动位置生成
     ///
     /// 生成棋盘上所有棋子可能移动的所有位置。
-    fn generate_all_moves(&mut self, side: &Side) -> Vec<MinMaxNode> {
-        let mut all_moves: Vec<MinMaxNode> = Vec::new();
+    fn generate_all_moves(&mut self, side: &Side) -> Vec<Move> {
+        let mut all_moves: Vec<Move> = Vec::new();
         for i in 0..MAX_CELLS_SIZE {
             if let Some(p) = self.positions[i] {
                 match side {
@@ -535,7 +2241,7 @@ impl Board {
     /// * `position_from` - 兵（卒）当前位置。
     fn generate_pawn(
         &mut self,
-        all_moves: &mut Vec<MinMaxNode>,
+        all_moves: &mut Vec<Move>,
         side: &Side,
         piece_from: char,
         position_from: usize,
@@ -591,12 +2297,12 @@ impl Board {
     /// * `position_from` - 炮当前位置。
     fn generate_cannon(
         &mut self,
-        all_moves: &mut Vec<MinMaxNode>,
+        all_moves: &mut Vec<Move>,
         piece_from: char,
         position_from: usize,
     ) {
         let skip: usize = 1usize;
-        let mut piece_moves: Vec<usize> =
+        let piece_moves: Vec<usize> =
             self.generate_piece_move_by_four_direction(position_from, skip);
 
         self.generate_general_moves(all_moves, piece_from, position_from, &piece_moves);
@@ -611,7 +2317,7 @@ impl Board {
     /// * `position_from` - 车（車）当前位置。
     fn generate_rook(
         &mut self,
-        all_moves: &mut Vec<MinMaxNode>,
+        all_moves: &mut Vec<Move>,
         piece_from: char,
         position_from: usize,
     ) {
@@ -718,6 +2424,7 @@ impl Board {
     /// * `column_positions` - 棋子所在列的所有位置坐标。
     /// * `piece_moves` - 棋子所有移动坐标。
     /// * `skip` - 跳过棋子数（炮:1,车:0）。
+    #[allow(clippy::too_many_arguments)]
     fn generate_piece_move_by_line(
         &self,
         start: usize,
@@ -772,10 +2479,77 @@ impl Board {
     /// * `position_from` - 马（馬）当前位置。
     fn generate_knight(
         &mut self,
-        all_moves: &mut Vec<MinMaxNode>,
+        all_moves: &mut Vec<Move>,
         piece_from: char,
         position_from: usize,
     ) {
+        let piece_moves: Vec<usize> = self.knight_targets(position_from);
+        self.generate_general_moves(all_moves, piece_from, position_from, &piece_moves);
+    }
+
+    /// 可以到达的位置统计
+    ///
+    /// 返回可以到达的位置数（空位以及对方棋子）和其中对方棋子的个数。
+    ///
+    /// * `piece` - 棋子。
+    /// * `targets` - 移动位置。
+    fn count_targets(&self, piece: char, targets: &[usize]) -> (i32, i32) {
+        let mut mobility = 0i32;
+        let mut captures = 0i32;
+        for target in targets {
+            match self.positions[*target] {
+                None => mobility += 1i32,
+                Some(p) if self.is_not_same_side(piece, p) => {
+                    mobility += 1i32;
+                    captures += 1i32;
+                }
+                _ => {}
+            }
+        }
+        (mobility, captures)
+    }
+
+    /// 直线可以到达的位置统计（车/炮）
+    ///
+    /// 与`generate_piece_move_by_four_direction`相同的规则，不生成位置只统计个数（评价用）。
+    ///
+    /// * `piece` - 棋子。
+    /// * `position_from` - 棋子当前位置。
+    /// * `skip` - 跳过棋子数（炮:1,车:0）。
+    fn count_line_targets(&self, piece: char, position_from: usize, skip: usize) -> (i32, i32) {
+        let mut mobility = 0i32;
+        let mut captures = 0i32;
+        for (row_step, column_step) in [(-1i32, 0i32), (1, 0), (0, 1), (0, -1)].iter() {
+            let mut row: i32 = INDEX_ROW[position_from] as i32 + row_step;
+            let mut column: i32 = INDEX_COLUMN[position_from] as i32 + column_step;
+            let mut screens = 0usize;
+            while let Some(position) = self.get_position(row, column) {
+                match self.positions[position] {
+                    // 炮架后面的空位不能到达
+                    None if screens == 0usize => mobility += 1i32,
+                    None => {}
+                    Some(p) if screens == skip => {
+                        if self.is_not_same_side(piece, p) {
+                            mobility += 1i32;
+                            captures += 1i32;
+                        }
+                        break;
+                    }
+                    Some(_) => screens += 1usize,
+                }
+                row += row_step;
+                column += column_step;
+            }
+        }
+        (mobility, captures)
+    }
+
+    /// 马（馬）可能移动位置取得
+    ///
+    /// 返回马（馬）可以到达的位置（过滤棋盘外以及马腿有棋子的位置，不区分红黑）。
+    ///
+    /// * `position_from` - 马（馬）当前位置。
+    fn knight_targets(&self, position_from: usize) -> Vec<usize> {
         // 0~9
         let row_number = INDEX_ROW[position_from];
         // 0~8
@@ -823,7 +2597,7 @@ impl Board {
             }
         }
 
-        self.generate_general_moves(all_moves, piece_from, position_from, &piece_moves);
+        piece_moves
     }
 
     /// 相（象）可能移动位置生成
@@ -835,11 +2609,18 @@ impl Board {
     /// * `position_from` - 相（象）当前位置。
     fn generate_bishop(
         &mut self,
-        all_moves: &mut Vec<MinMaxNode>,
+        all_moves: &mut Vec<Move>,
         piece_from: char,
         position_from: usize,
     ) {
-        let piece_moves = MOVES_BISHOP.get(&position_from).unwrap();
+        // 过滤象眼有棋子位置
+        let piece_moves: Vec<usize> = MOVES_BISHOP
+            .get(&position_from)
+            .unwrap()
+            .iter()
+            .filter(|position_to| self.is_empty((position_from + **position_to) / 2usize))
+            .cloned()
+            .collect();
 
         self.generate_general_moves(all_moves, piece_from, position_from, &piece_moves);
     }
@@ -853,13 +2634,13 @@ impl Board {
     /// * `position_from` - 士（仕）当前位置。
     fn generate_adviser(
         &mut self,
-        all_moves: &mut Vec<MinMaxNode>,
+        all_moves: &mut Vec<Move>,
         piece_from: char,
         position_from: usize,
     ) {
         let piece_moves = MOVES_ADVISER.get(&position_from).unwrap();
 
-        self.generate_general_moves(all_moves, piece_from, position_from, &piece_moves);
+        self.generate_general_moves(all_moves, piece_from, position_from, piece_moves);
     }
 
     /// 帅（将）可能移动位置生成
@@ -871,12 +2652,12 @@ impl Board {
     /// * `position_from` - 帅（将）当前位置。
     fn generate_king(
         &mut self,
-        all_moves: &mut Vec<MinMaxNode>,
+        all_moves: &mut Vec<Move>,
         piece_from: char,
         position_from: usize,
     ) {
         let piece_moves = MOVES_KING.get(&position_from).unwrap();
-        self.generate_general_moves(all_moves, piece_from, position_from, &piece_moves);
+        self.generate_general_moves(all_moves, piece_from, position_from, piece_moves);
     }
 
     /// 固定坐标移动棋子可能移动位置生成
@@ -889,13 +2670,12 @@ impl Board {
     /// * `piece_moves` - 棋子所有移动位置。
     fn generate_general_moves(
         &mut self,
-        all_moves: &mut Vec<MinMaxNode>,
+        all_moves: &mut Vec<Move>,
         piece_from: char,
         position_from: usize,
-        piece_moves: &Vec<usize>,
+        piece_moves: &[usize],
     ) {
-        for i in 0usize..piece_moves.len() {
-            let position_to: usize = piece_moves[i];
+        for &position_to in piece_moves {
             let piece_to: Option<char> = self.positions[position_to];
             if self.is_empty(position_to) || self.is_not_same_side(piece_from, piece_to.unwrap()) {
                 let (red_king_position, black_king_position) = match piece_from {
@@ -904,7 +2684,7 @@ impl Board {
                     _ => (None, None),
                 };
                 if self.king_facing_check(red_king_position, black_king_position) {
-                    all_moves.push(MinMaxNode::new(piece_from, position_from, position_to));
+                    all_moves.push(Move::new(piece_from, position_from, position_to));
                 }
             }
         }
@@ -935,8 +2715,8 @@ impl Board {
             let column_positions = &INDEX_COLUMN_POSITIONS[red_king_column];
             let red_king_row: usize = INDEX_ROW[red_king_position];
             let black_king_row: usize = INDEX_ROW[black_king_position];
-            for i in (black_king_row + 1usize)..red_king_row {
-                if self.positions[column_positions[i]].is_some() {
+            for position in &column_positions[(black_king_row + 1usize)..red_king_row] {
+                if self.positions[*position].is_some() {
                     return true;
                 }
             }
@@ -1001,6 +2781,20 @@ impl Board {
         piece_from.is_ascii_lowercase() != piece_to.is_ascii_lowercase()
     }
 
+    /// 坐标位置取得
+    ///
+    /// 根据行列取得位置，超出棋盘时返回None。
+    ///
+    /// * `row` - 行（0~9）。
+    /// * `column` - 列（0~8）。
+    fn get_position(&self, row: i32, column: i32) -> Option<usize> {
+        if row < 0 || row >= HEIGHT as i32 || column < 0 || column >= WIDTH as i32 {
+            None
+        } else {
+            Some(INDEX_ROW_POSITIONS[row as usize][column as usize])
+        }
+    }
+
     /// 棋子检测
     ///
     /// 检测指定位置是否存在棋子。
@@ -1015,18 +2809,21 @@ impl Board {
     /// 将棋盘上的棋子全部清理。
     fn clear(&mut self) {
         self.pieces_count = 0usize;
+        self.initial_plies = 0usize;
+        self.key = 0u64;
+        self.history.clear();
         for i in 0usize..MAX_CELLS_SIZE {
             self.positions[i] = None;
         }
     }
 
-    fn test_print_all_moves(&self, mark: &str, all_moves: &Vec<MinMaxNode>) {
+    fn test_print_all_moves(&self, mark: &str, all_moves: &Vec<Move>) {
         for node in all_moves {
             println!("{}---piece=[{}],from=[{}],to=[{}]", mark, node.piece, node.from, node.to);
         }
     }
 
-    fn test_print_node(&self, mark: &str, node: &MinMaxNode, value: i32) {
+    fn test_print_node(&self, mark: &str, node: &Move, value: i32) {
         println!("{}---piece=[{}],from=[{}][{}],to=[{}][{}],value=[{}]",
                  mark,
                  node.piece,
@@ -1037,36 +2834,516 @@ impl Board {
                  value);
     }
 
+}
 
-    fn test_print_fen(&self, mark: &str) {
-        let mut fen = String::new();
-        let mut space = 0usize;
-        for i in 0usize..MAX_CELLS_SIZE {
-            match self.positions[i] {
-                None => {
-                    space += 1;
-                    if i == 89usize {
-                        fen.push_str(&space.to_string());
-                    }
-                }
-                Some(p) => {
-                    if space > 0usize {
-                        fen.push_str(&space.to_string());
-                        space = 0usize;
-                    }
-                    fen.push_str(&p.to_string());
-                }
+/// 棋子种类取得
+///
+/// 返回棋子的种类（0~6：帅 士 相 马 车 炮 兵），红黑相同。
+///
+/// * `piece` - 棋子。
+fn piece_kind(piece: char) -> usize {
+    match piece.to_ascii_uppercase() {
+        RED_KING => 0usize,
+        RED_ADVISER => 1usize,
+        RED_BISHOP => 2usize,
+        RED_KNIGHT => 3usize,
+        RED_ROOK => 4usize,
+        RED_CANNON => 5usize,
+        _ => 6usize,
+    }
+}
+
+/// Zobrist键值取得
+///
+/// * `piece` - 棋子。
+/// * `position` - 位置。
+fn zobrist(piece: char, position: usize) -> u64 {
+    match ZOBRIST_PIECES.find(piece) {
+        Some(index) => ZOBRIST[index][position],
+        None => 0u64,
+    }
+}
+
+/// 置换表保存用的评价值
+///
+/// 将死的评价值转换为距该局面的步数（检索中为距根节点的步数）。
+///
+/// * `value` - 评价值。
+/// * `ply` - 该局面距根节点的步数。
+fn value_to_table(value: i32, ply: i32) -> i32 {
+    if value > MATE_BOUND {
+        value + ply
+    } else if value < -MATE_BOUND {
+        value - ply
+    } else {
+        value
+    }
+}
+
+/// 置换表读取的评价值
+///
+/// `value_to_table`的逆转换。
+///
+/// * `value` - 置换表的评价值。
+/// * `ply` - 该局面距根节点的步数。
+fn value_from_table(value: i32, ply: i32) -> i32 {
+    if value > MATE_BOUND {
+        value - ply
+    } else if value < -MATE_BOUND {
+        value + ply
+    } else {
+        value
+    }
+}
+
+/// 兵（卒）前进检测
+///
+/// 兵（卒）前进为不可逆着法（横走可逆）。
+///
+/// * `node` - 着法。
+fn is_pawn_advance(node: &Move) -> bool {
+    (node.piece == RED_PAWN || node.piece == BLACK_PAWN)
+        && INDEX_ROW[node.from] != INDEX_ROW[node.to]
+}
+
+/// 着法分类的较低者
+///
+/// 循环中一方的分类取所有着法中最低的分类（一将一捉为长捉）。
+fn min_kind(a: RepetitionKind, b: RepetitionKind) -> RepetitionKind {
+    if a < b {
+        a
+    } else {
+        b
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SYMMETRY_FENS: [&str; 5] = [
+        START_FEN,
+        "r1bakab1r/9/1cn3nc1/p1p1p1p1p/9/9/P1P1P1P1P/1C2C1N2/9/RNBAKAB1R b",
+        "2bakab2/9/2n1c4/p3p1p1p/2p6/6P2/P1P1P3P/2N1C4/4A4/2BAK1B2 w",
+        "3k5/4a4/4b4/9/2p6/9/9/4B4/4A4/3AK1B2 w",
+        "4k4/R8/9/9/9/9/9/9/9/3K4R w",
+    ];
+
+    fn evaluate_fen(fen: &str) -> i32 {
+        let mut board = Board::new();
+        board.init_board(fen);
+        board.evaluate()
+    }
+
+    /// 红黑交换（上下翻转并交换大小写）
+    fn flip_colors(fen: &str) -> String {
+        let placement: &str = fen.split_whitespace().next().unwrap();
+        let ranks: Vec<String> = placement
+            .split('/')
+            .rev()
+            .map(|rank| {
+                rank.chars()
+                    .map(|c| {
+                        if c.is_ascii_uppercase() {
+                            c.to_ascii_lowercase()
+                        } else {
+                            c.to_ascii_uppercase()
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        ranks.join("/")
+    }
+
+    /// 左右翻转
+    fn mirror_files(fen: &str) -> String {
+        let placement: &str = fen.split_whitespace().next().unwrap();
+        let ranks: Vec<String> = placement
+            .split('/')
+            .map(|rank| rank.chars().rev().collect())
+            .collect();
+        ranks.join("/")
+    }
+
+    #[test]
+    fn default_params_are_valid() {
+        assert_eq!(EvaluateParams::default().validate(), Ok(()));
+    }
+
+    #[test]
+    fn evaluate_flipped_colors_is_negation() {
+        for fen in SYMMETRY_FENS.iter() {
+            assert_eq!(evaluate_fen(&flip_colors(fen)), -evaluate_fen(fen), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn evaluate_mirrored_files_is_equal() {
+        for fen in SYMMETRY_FENS.iter() {
+            assert_eq!(evaluate_fen(&mirror_files(fen)), evaluate_fen(fen), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn evaluate_without_phase_uses_endgame_values() {
+        // 红帅e0、红兵e3、黑将d9，阶段值为0
+        let params: EvaluateParams = EvaluateParams::default();
+        let weight: i32 = params.position_weight;
+        let eg = &params.endgame;
+        let red: i32 = eg.material[0] + eg.king[85] * weight + eg.material[6] + eg.pawn[58] * weight;
+        let black: i32 = eg.material[0] + eg.king[89 - 3] * weight;
+        assert_eq!(evaluate_fen("3k5/9/9/9/9/9/4P4/9/9/4K4 w"), black - red);
+    }
+
+    #[test]
+    fn knight_mobility_respects_leg_blocking() {
+        let mut board = Board::new();
+        // 红马e4（49），马腿没有棋子时可以到达8个位置
+        board.init_board("3k5/9/9/9/9/4N4/9/9/9/4K4 w");
+        let mobility: i32 = board.params.mobility[3];
+        assert_eq!(board.activity(49, RED_KNIGHT).0, 8 * mobility);
+        // e5（40）有棋子时不能向上跳
+        board.init_board("3k5/9/9/9/4p4/4N4/9/9/9/4K4 w");
+        assert_eq!(board.activity(49, RED_KNIGHT).0, 6 * mobility);
+    }
+
+    #[test]
+    fn king_safety_penalizes_rook_on_king_file() {
+        let mut board = Board::new();
+        board.init_board("3akab2/9/4b4/9/9/9/9/9/4A4/1r1AK1B2 w");
+        let quiet: i32 = board.king_safety(Side::Red);
+        board.init_board("3akab2/9/4b4/9/9/9/9/9/4A4/3AK1B2 w");
+        let without_rook: i32 = board.king_safety(Side::Red);
+        board.init_board("3akab2/9/4b4/9/4r4/9/9/9/4A4/3AK1B2 w");
+        let rook_on_file: i32 = board.king_safety(Side::Red);
+        assert_eq!(quiet, without_rook);
+        assert_eq!(rook_on_file, without_rook - board.params.king_safety.rook_on_king_file);
+    }
+
+    #[test]
+    fn evaluate_start_position_is_zero() {
+        assert_eq!(evaluate_fen(START_FEN), 0i32);
+    }
+
+    #[test]
+    fn random_choice_stays_within_margin() {
+        let margin: i32 = 30i32;
+        let mut best = Board::new();
+        best.init_board(START_FEN);
+        let best_move: Move = best.search_by_depth(2usize).unwrap();
+        let best_score: i32 = best.score();
+        let mut moves: Vec<Move> = Vec::new();
+        for seed in 0u64..20u64 {
+            let mut board = Board::new();
+            board.init_board(START_FEN);
+            board.set_randomness(Randomness { margin, seed });
+            let node: Move = board.search_by_depth(2usize).unwrap();
+            assert!(best_score - board.score() <= margin);
+            // 相同的种子选择相同的着法
+            let mut replay = Board::new();
+            replay.init_board(START_FEN);
+            replay.set_randomness(Randomness { margin, seed });
+            assert_eq!(replay.search_by_depth(2usize), Some(node));
+            moves.push(node);
+        }
+        assert!(moves.iter().any(|m| *m != best_move));
+    }
+
+    #[test]
+    fn skill_elo_is_relative_to_strongest() {
+        assert_eq!(Skill { level: MAX_SKILL_LEVEL, seed: 1u64 }.elo(), MAX_SKILL_ELO);
+        assert!(Skill { level: 0usize, seed: 1u64 }.elo() < Skill { level: 5usize, seed: 1u64 }.elo());
+        assert!(Skill { level: 5usize, seed: 1u64 }.elo() < MAX_SKILL_ELO);
+    }
+
+    #[test]
+    fn skill_limits_depth_and_noise() {
+        let mut board = Board::new();
+        board.init_board(START_FEN);
+        board.set_skill(Skill { level: 0usize, seed: 1u64 });
+        assert!(board.search_by_limit(SearchLimit::depth(4usize)).is_some());
+        assert_eq!(board.depth(), 1usize);
+        let noise: i32 = SKILL_LEVELS[0].2;
+        assert!(board.noise().abs() <= noise);
+
+        // 最强级别与不设置时相同
+        board.set_skill(Skill { level: MAX_SKILL_LEVEL, seed: 1u64 });
+        let strongest: Option<Move> = board.search_by_limit(SearchLimit::depth(3usize));
+        assert_eq!(board.depth(), 3usize);
+        assert_eq!(board.noise(), 0i32);
+        let mut full = Board::new();
+        full.init_board(START_FEN);
+        assert_eq!(full.search_by_limit(SearchLimit::depth(3usize)), strongest);
+    }
+
+    #[test]
+    fn parallel_search_finds_mate() {
+        let mut board = Board::new();
+        board.init_board("4k4/9/9/9/9/9/9/9/R8/3K4R w");
+        board.search_by_depth(4usize).unwrap();
+        let score: i32 = board.score();
+        assert!(score > MATE_BOUND);
+
+        let mut parallel = Board::new();
+        parallel.init_board("4k4/9/9/9/9/9/9/9/R8/3K4R w");
+        parallel.set_threads(3usize);
+        assert!(parallel.search_by_depth(4usize).is_some());
+        assert_eq!(parallel.score(), score);
+        assert!(!parallel.stop.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn principal_variation_leads_to_mate() {
+        let mut board = Board::new();
+        board.init_board("4k4/9/9/9/9/9/9/9/R8/3K4R w");
+        let best: Move = board.search_by_depth(4usize).unwrap();
+        let line: Vec<Move> = board.principal_variation().to_vec();
+        assert_eq!(line.first(), Some(&best));
+        // 主要变例的各着法都合法，走完之后红方将死黑方
+        let mut side: Side = board.side;
+        for node in line.iter() {
+            assert!(board.generate_legal_moves(&side).contains(node), "{:?}", node);
+            board.do_move(node);
+            side = side.opponent();
+        }
+        assert_eq!(line.len() as i32, MATE_VALUE - board.score());
+        assert!(board.generate_legal_moves(&side).is_empty());
+    }
+
+    #[test]
+    fn root_moves_are_sorted_with_best_first() {
+        let mut board = Board::new();
+        board.init_board(START_FEN);
+        let best: Move = board.search_by_depth(3usize).unwrap();
+        let count: usize = board.generate_legal_moves(&Side::Red).len();
+        let roots: &[RootMove] = board.root_moves();
+        assert_eq!(roots.len(), count);
+        assert_eq!(roots[0].node, best);
+        assert_eq!(roots[0].score, board.score());
+        assert!(roots.windows(2usize).all(|w| w[0].score >= w[1].score));
+        assert!(roots.iter().all(|root| root.pv.first() == Some(&root.node)));
+    }
+
+    #[test]
+    fn search_stops_on_node_limit_and_flag() {
+        let mut board = Board::new();
+        board.init_board(START_FEN);
+        assert!(board.search_by_limit(SearchLimit::nodes(5000u64)).is_some());
+        assert!(board.nodes() <= 5000u64 + LIMIT_CHECK_MASK);
+        assert!(board.depth() >= 1usize);
+        assert!(!board.stop.load(Ordering::Relaxed));
+
+        // 停止标志被设置时第一层之后的检索中止，使用第一层的结果
+        let mut board = Board::new();
+        board.init_board(START_FEN);
+        board.halt_flag().store(true, Ordering::Relaxed);
+        assert!(board.search_by_limit(SearchLimit::infinite()).is_some());
+        assert_eq!(board.depth(), 1usize);
+        assert_eq!(board.principal_variation().len(), 1usize);
+    }
+
+    #[test]
+    fn mate_distance_pruning_keeps_shortest_mate() {
+        // 一步杀：更深的检索也不会用更慢的将死替换
+        for depth in 1usize..=5usize {
+            let mut board = Board::new();
+            board.init_board("4k4/R8/9/9/9/9/9/9/9/3K4R w");
+            board.search_by_depth(depth).unwrap();
+            assert_eq!(board.score(), MATE_VALUE - 1i32, "depth {}", depth);
+        }
+
+        // 要求比一步杀更好结果的窗口在根节点就剪枝
+        let mut board = Board::new();
+        board.init_board("4k4/R8/9/9/9/9/9/9/9/3K4R w");
+        let bound: i32 = MATE_VALUE - 1i32;
+        assert_eq!(board.min_max(5usize, -bound, -MATE_VALUE, &Side::Red), -bound);
+        assert_eq!(board.nodes(), 1u64);
+        let mut board = Board::new();
+        board.init_board("4k4/R8/9/9/9/9/9/9/9/3K4R b");
+        assert_eq!(board.min_max(5usize, MATE_VALUE, bound, &Side::Black), bound);
+        assert_eq!(board.nodes(), 1u64);
+    }
+
+    #[test]
+    fn selective_search_keeps_mate_and_saves_nodes() {
+        assert_eq!(Selectivity::parse("all"), Ok(Selectivity::all()));
+        assert_eq!(
+            Selectivity::parse("futility,pvs"),
+            Ok(Selectivity { futility: true, pvs: true, ..Selectivity::none() })
+        );
+        assert!(Selectivity::parse("null,unknown").is_err());
+
+        let mut nodes: Vec<u64> = Vec::new();
+        for selectivity in &[Selectivity::none(), Selectivity::all()] {
+            let mut board = Board::new();
+            board.init_board("4k4/9/9/9/9/9/9/9/R8/3K4R w");
+            board.set_selectivity(*selectivity);
+            board.search_by_depth(4usize).unwrap();
+            assert_eq!(board.score(), MATE_VALUE - 3i32);
+
+            let mut board = Board::new();
+            board.init_board("2bak4/4a4/4b4/p1p1p3p/9/2P3p2/P3P3P/1C2B4/4A4/1NBK1A3 w");
+            board.set_selectivity(*selectivity);
+            board.search_by_depth(4usize).unwrap();
+            nodes.push(board.nodes());
+        }
+        assert!(nodes[1] < nodes[0], "{:?}", nodes);
+    }
+
+    #[test]
+    fn extensions_find_check_mate_and_pvs_keeps_score() {
+        // 连将杀：没有将军延伸时深度4看不到
+        let fen: &str = "4ka3/4a4/4b4/9/9/9/9/9/4p4/R2K4R w";
+        let mut board = Board::new();
+        board.init_board(fen);
+        board.set_selectivity(Selectivity::none());
+        board.search_by_depth(4usize).unwrap();
+        assert!(board.score().abs() < MATE_BOUND);
+
+        let mut board = Board::new();
+        board.init_board(fen);
+        board.set_selectivity(Selectivity { extension: true, ..Selectivity::none() });
+        let best: Move = board.search_by_depth(4usize).unwrap();
+        assert_eq!(best.to_iccs(), "a0a9");
+        assert_eq!(board.score(), MATE_VALUE - 9i32);
+
+        // 主要变例检索（零窗口和重新检索）不改变固定深度的评价值
+        for fen in &[START_FEN, "2bak4/4a4/4b4/p1p1p3p/9/2P3p2/P3P3P/1C2B4/4A4/1NBK1A3 w"] {
+            let mut scores: Vec<i32> = Vec::new();
+            for selectivity in &[Selectivity::none(), Selectivity { pvs: true, ..Selectivity::none() }] {
+                let mut board = Board::new();
+                board.init_board(fen);
+                board.set_selectivity(*selectivity);
+                board.search_by_depth(3usize).unwrap();
+                scores.push(board.score());
             }
-            if i > 0usize && i % WIDTH == 8usize {
-                if space > 0usize {
-                    fen.push_str(&space.to_string());
-                    space = 0usize;
-                }
-                if i != 89usize {
-                    fen.push_str("/");
-                }
+            assert_eq!(scores[0], scores[1], "{}", fen);
+        }
+    }
+
+    #[test]
+    fn aspiration_fails_widen_the_window() {
+        let mut board = Board::new();
+        board.init_board(START_FEN);
+        board.set_selectivity(Selectivity::all());
+        board.search_by_limit(SearchLimit::depth(5usize)).unwrap();
+        let fails: Vec<AspirationFail> = board.aspiration_fails().to_vec();
+        assert!(!fails.is_empty());
+        for fail in &fails {
+            assert!(fail.depth >= ASPIRATION_MIN_DEPTH);
+            if fail.high {
+                assert!(fail.score >= fail.window.1, "{:?}", fail);
+            } else {
+                assert!(fail.score <= fail.window.0, "{:?}", fail);
             }
         }
-        println!("{}---{}", mark, fen)
+        // 失败后扩大窗口重新检索，完成指定的深度
+        assert_eq!(board.depth(), 5usize);
+        assert!(board.score().abs() < MATE_BOUND);
+
+        let mut board = Board::new();
+        board.init_board(START_FEN);
+        board.set_selectivity(Selectivity { aspiration: false, ..Selectivity::all() });
+        board.search_by_limit(SearchLimit::depth(5usize)).unwrap();
+        assert!(board.aspiration_fails().is_empty());
+    }
+
+    #[test]
+    fn perft_counts_start_position() {
+        let mut board = Board::new();
+        board.init_board(START_FEN);
+        assert_eq!(board.perft(1usize), 44u64);
+        assert_eq!(board.perft(2usize), 1920u64);
+        assert_eq!(board.perft(3usize), 79666u64);
+    }
+
+    #[test]
+    fn blocked_bishop_eye_and_knight_leg() {
+        // 相眼b1被马塞住，马腿h1被车蹩住
+        let mut board = Board::new();
+        board.init_board("3k5/9/9/9/9/9/9/9/1N5R1/2B1K2N1 w");
+        let moves: Vec<String> = board.generate_legal_moves(&Side::Red).iter().map(|m| m.to_iccs()).collect();
+        let from = |square: &str| -> Vec<String> {
+            moves.iter().filter(|m| m.starts_with(square)).cloned().collect()
+        };
+        assert_eq!(from("c0"), vec![String::from("c0e2")]);
+        assert_eq!(from("h0"), vec![String::from("h0f1")]);
+    }
+
+    fn classify_move(fen: &str, rule: RepetitionRule, iccs: &str) -> RepetitionKind {
+        let mut board = Board::new();
+        board.init_board(fen);
+        board.set_rule(rule);
+        let (from, to) = board.parse_iccs(iccs).unwrap();
+        board.make_move(from, to);
+        let node: Move = board.history.last().unwrap().node;
+        board.classify(&node)
+    }
+
+    #[test]
+    fn classify_check_chase_and_idle() {
+        let asian: RepetitionRule = RepetitionRule::Asian;
+        // 将军
+        assert_eq!(classify_move("4k4/9/9/9/9/9/9/9/9/R2K5 w", asian, "a0a9"), RepetitionKind::Check);
+        // 捉无保护的马
+        assert_eq!(classify_move("4k4/9/9/9/2n6/9/9/9/9/R2K5 w", asian, "a0a5"), RepetitionKind::Chase);
+        // 有保护的马不算捉
+        assert_eq!(classify_move("2r1k4/9/9/9/2n6/9/9/9/9/R2K5 w", asian, "a0a5"), RepetitionKind::Idle);
+        // 马捉车即使有保护也算捉
+        assert_eq!(classify_move("2r1k4/9/9/9/2r6/9/9/9/4N4/3K5 w", asian, "e1d3"), RepetitionKind::Chase);
+        // 兵捉子不算捉
+        assert_eq!(classify_move("4k4/9/9/1Pn6/9/9/9/9/9/3K5 w", asian, "b6b7"), RepetitionKind::Idle);
+        // 什么也不攻击
+        assert_eq!(classify_move("4k4/9/9/9/9/9/9/9/9/R2K5 w", asian, "a0a1"), RepetitionKind::Idle);
+    }
+
+    #[test]
+    fn classify_counts_only_new_attacks() {
+        let asian: RepetitionRule = RepetitionRule::Asian;
+        // 马让开后车捉无保护的炮（闪捉）
+        assert_eq!(classify_move("4k4/9/c8/9/9/9/N8/9/9/R2K5 w", asian, "a3c4"), RepetitionKind::Chase);
+        // 移动前已经在捉的炮不算新的捉
+        assert_eq!(classify_move("4k4/9/9/9/R1n6/9/9/9/9/3K5 w", asian, "a5b5"), RepetitionKind::Idle);
+        assert_eq!(classify_move("4k4/9/9/9/R1n6/9/9/9/9/3K5 w", asian, "d0d1"), RepetitionKind::Idle);
+    }
+
+    #[test]
+    fn chase_of_crossed_pawn_depends_on_rule() {
+        let fen: &str = "4k4/9/9/9/P8/9/9/9/8r/3K5 b";
+        assert_eq!(classify_move(fen, RepetitionRule::Asian, "i1i5"), RepetitionKind::Chase);
+        assert_eq!(classify_move(fen, RepetitionRule::Chinese, "i1i5"), RepetitionKind::Idle);
+
+        let mut board = Board::new();
+        board.init_board(START_FEN);
+        let square = |iccs: &str| -> usize { board.parse_iccs(&format!("{}a0", iccs)).unwrap().0 };
+        let (crossed, home, black_crossed, king, rook) =
+            (square("a5"), square("a3"), square("a4"), square("e0"), square("a0"));
+        assert!(board.is_chase_target(RED_PAWN, crossed));
+        assert!(!board.is_chase_target(RED_PAWN, home));
+        assert!(board.is_chase_target(BLACK_PAWN, black_crossed));
+        assert!(!board.is_chase_target(RED_KING, king));
+        assert!(board.is_chase_target(RED_ROOK, rook));
+        board.set_rule(RepetitionRule::Chinese);
+        assert!(!board.is_chase_target(RED_PAWN, crossed));
+        assert!(!board.is_chase_target(BLACK_PAWN, black_crossed));
+        assert!(board.is_chase_target(RED_ROOK, rook));
+    }
+
+    #[test]
+    fn evaluate_trace_totals_match_evaluate() {
+        for fen in SYMMETRY_FENS.iter() {
+            let mut board = Board::new();
+            board.init_board(fen);
+            let trace: EvaluateTrace = board.evaluate_trace();
+            let middlegame: i32 = trace.black.middlegame() - trace.red.middlegame();
+            let endgame: i32 = trace.black.endgame() - trace.red.endgame();
+            let activity: i32 = trace.black.activity() - trace.red.activity();
+            let phase: i32 = trace.phase;
+            let total: i32 = trace.total_phase;
+            let score: i32 = (middlegame * phase + endgame * (total - phase)) / total + activity;
+            assert_eq!(score, board.evaluate(), "{}", fen);
+            assert_eq!(trace.score, score, "{}", fen);
+        }
     }
 }
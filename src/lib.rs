@@ -0,0 +1,43 @@
+//! 中国象棋引擎
+//!
+//! 由纯Rust写成的中国象棋引擎，提供局面、着法生成和检索。
+//!
+//! ```
+//! use chess::{Position, SearchLimit};
+//!
+//! let mut position = Position::new();
+//! let m = position.parse_move("h2e2").unwrap();
+//! assert_eq!(position.to_chinese(&m), "炮二平五");
+//! position.make_move(m).unwrap();
+//!
+//! let m = position.parse_chinese("马8进7").unwrap();
+//! position.make_move(m).unwrap();
+//!
+//! let result = position.search(SearchLimit::depth(2)).unwrap();
+//! println!("bestmove {} score {}", result.best_move.to_iccs(), result.score);
+//! ```
+
+extern crate serde;
+extern crate serde_json;
+extern crate serde_yaml;
+
+#[macro_use]
+extern crate serde_derive;
+
+#[macro_use]
+extern crate lazy_static;
+
+mod game;
+mod notation;
+mod params;
+mod position;
+mod tt;
+
+pub use game::{
+    AspirationFail, EvaluateTrace, Move, PieceTrace, Randomness, RepetitionRule, RootMove, SearchLimit, Selectivity,
+    Side, SideTrace, Skill, HEIGHT, KING_SAFETY_TERMS, MATE_BOUND, MATE_VALUE, MAX_CELLS_SIZE, MAX_SEARCH_THREADS,
+    MAX_SKILL_ELO, MAX_SKILL_LEVEL, START_FEN, WIDTH,
+};
+pub use params::{EvaluateParams, KingSafetyParams, PhaseParams};
+pub use position::{GameStatus, Position, Reason, SearchResult};
+pub use tt::{TranspositionTable, DEFAULT_HASH_SIZE, MAX_HASH_SIZE};
@@ -1,20 +1,32 @@
-#![allow(unused_imports, dead_code, unused_mut, unused_variables)]
-
+extern crate chess;
 extern crate serde;
 extern crate serde_yaml;
 
 #[macro_use]
 extern crate serde_derive;
 
-#[macro_use]
-extern crate lazy_static;
-
-mod game;
+mod bench;
+mod cli;
+mod epd;
+mod play;
 mod server;
+mod tune;
+mod ucci;
+
+use std::env;
+use std::process;
 
 fn main() {
-    let mut server = server::Server::new("resources/server_init.yaml");
-    server.startup();
+    let args: Vec<String> = env::args().skip(1).collect();
+    let arguments: cli::Arguments = match cli::parse(&args) {
+        Ok(a) => a,
+        Err(message) => {
+            eprintln!("error: {}\n\n{}", message, cli::usage());
+            process::exit(1);
+        }
+    };
+    if let Err(message) = cli::execute(arguments) {
+        eprintln!("error: {}", message);
+        process::exit(1);
+    }
 }
-
-
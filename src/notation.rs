@@ -0,0 +1,276 @@
+use game::*;
+use position::Position;
+
+/**************************************************************************************************/
+/*******************************   NOTATION DEFINITION    *****************************************/
+/**************************************************************************************************/
+// 帅 士 相 马 车 炮 兵
+const RED_NAMES: [char; 7] = ['帅', '仕', '相', '马', '车', '炮', '兵'];
+const BLACK_NAMES: [char; 7] = ['将', '士', '象', '马', '车', '炮', '卒'];
+const PIECES: &str = "KABNRCP";
+
+const RED_NUMBERS: [char; 9] = ['一', '二', '三', '四', '五', '六', '七', '八', '九'];
+
+const ACTION_FORWARD: char = '进';
+const ACTION_BACKWARD: char = '退';
+const ACTION_SIDEWAYS: char = '平';
+
+// 同一列有两个或三个同种棋子时的前后表示
+const ORDER_TWO: [char; 2] = ['前', '后'];
+const ORDER_THREE: [char; 3] = ['前', '中', '后'];
+
+// 解析时统一成同一个字符（繁体、红黑不同的棋子名、数字）
+const NORMALIZE: [(&str, char); 22] = [
+    ("帅帥将將", 'K'),
+    ("仕士", 'A'),
+    ("相象", 'B'),
+    ("马馬傌", 'N'),
+    ("车車俥", 'R'),
+    ("炮砲包", 'C'),
+    ("兵卒", 'P'),
+    ("进進", '+'),
+    ("退", '-'),
+    ("平", '='),
+    ("前", 'f'),
+    ("中", 'm'),
+    ("后後", 'r'),
+    ("一１", '1'),
+    ("二２", '2'),
+    ("三３", '3'),
+    ("四４", '4'),
+    ("五５", '5'),
+    ("六６", '6'),
+    ("七７", '7'),
+    ("八８", '8'),
+    ("九９", '9'),
+];
+
+impl Position {
+    /// 中文纵线着法转换
+    ///
+    /// 将着法转换成中文纵线格式（例：炮二平五、马8进7、前车退二）。
+    /// 红方用中文数字从右向左数列，黑方用阿拉伯数字从（黑方的）右向左数列。
+    /// 兵（卒）在两列以上各有两个以上时，用列代替棋子名（例：前七进一）。
+    ///
+    /// * `m` - 着法。
+    pub fn to_chinese(&self, m: &Move) -> String {
+        let is_red: bool = m.piece.is_ascii_uppercase();
+        let kind: usize = PIECES.find(m.piece.to_ascii_uppercase()).unwrap_or(0usize);
+        let name: char = if is_red { RED_NAMES[kind] } else { BLACK_NAMES[kind] };
+        let (row_from, column_from) = (m.from / WIDTH, m.from % WIDTH);
+        let (row_to, column_to) = (m.to / WIDTH, m.to % WIDTH);
+        let number = |n: usize| -> String {
+            if is_red {
+                RED_NUMBERS[n - 1usize].to_string()
+            } else {
+                n.to_string()
+            }
+        };
+        let file = |column: usize| -> usize {
+            if is_red {
+                WIDTH - column
+            } else {
+                column + 1usize
+            }
+        };
+
+        let mut notation = String::new();
+
+        // 同一列的同种棋子，按从前到后排列
+        let mut same_file: Vec<usize> = (0usize..HEIGHT)
+            .map(|row| row * WIDTH + column_from)
+            .filter(|square| self.piece_at(*square) == Some(m.piece))
+            .collect();
+        if !is_red {
+            same_file.reverse();
+        }
+        let order: usize = same_file.iter().position(|s| *s == m.from).unwrap_or(0usize);
+        // 其它列也有两个以上同种棋子（兵、卒）时，用列代替棋子名（例：前七进一）
+        let crowded: bool = (0usize..WIDTH).filter(|column| *column != column_from).any(|column| {
+            (0usize..HEIGHT).filter(|row| self.piece_at(row * WIDTH + column) == Some(m.piece)).count() >= 2usize
+        });
+        let label: String = if crowded { number(file(column_from)) } else { name.to_string() };
+        match same_file.len() {
+            0 | 1 => {
+                notation.push(name);
+                notation.push_str(&number(file(column_from)));
+            }
+            2 => {
+                notation.push(ORDER_TWO[order]);
+                notation.push_str(&label);
+            }
+            3 => {
+                notation.push(ORDER_THREE[order]);
+                notation.push_str(&label);
+            }
+            _ => {
+                notation.push_str(&number(order + 1usize));
+                notation.push(name);
+            }
+        }
+
+        if row_from == row_to {
+            notation.push(ACTION_SIDEWAYS);
+            notation.push_str(&number(file(column_to)));
+        } else {
+            let forward: bool = if is_red { row_to < row_from } else { row_to > row_from };
+            notation.push(if forward { ACTION_FORWARD } else { ACTION_BACKWARD });
+            // 帅、车、炮、兵直走时表示步数，仕、相、马表示到达的列
+            match m.piece.to_ascii_uppercase() {
+                'A' | 'B' | 'N' => notation.push_str(&number(file(column_to))),
+                _ => notation.push_str(&number(row_from.abs_diff(row_to))),
+            }
+        }
+        notation
+    }
+
+    /// 变例的中文纵线格式表示
+    ///
+    /// 从当前局面依次走出各着法并转换成中文纵线格式，结束后恢复局面。
+    /// 遇到不合法的着法时到此为止。
+    ///
+    /// * `line` - 着法列表（例如主要变例）。
+    pub fn line_to_chinese(&mut self, line: &[Move]) -> Vec<String> {
+        let mut texts: Vec<String> = Vec::new();
+        for m in line.iter() {
+            let text: String = self.to_chinese(m);
+            if self.make_move(*m).is_err() {
+                break;
+            }
+            texts.push(text);
+        }
+        for _ in texts.iter() {
+            self.undo_move();
+        }
+        texts
+    }
+
+    /// 中文纵线着法解析
+    ///
+    /// 将中文纵线格式的着法解析成合法着法，接受繁体字、全角数字以及红黑通用的棋子名。
+    /// 格式错误或者不合法时返回Err。
+    ///
+    /// * `text` - 中文纵线格式的着法。
+    pub fn parse_chinese(&mut self, text: &str) -> Result<Move, String> {
+        let target: String = normalize(text);
+        if target.chars().count() != 4usize {
+            return Err(format!("invalid move [{}]", text));
+        }
+        self.legal_moves()
+            .into_iter()
+            .find(|m| normalize(&self.to_chinese(m)) == target)
+            .ok_or_else(|| format!("illegal move [{}]", text))
+    }
+
+    /// 着法解析（ICCS或者中文纵线格式）
+    ///
+    /// 全部为ASCII字符时按ICCS格式解析，否则按中文纵线格式解析。
+    ///
+    /// * `text` - 着法。
+    pub fn parse_notation(&mut self, text: &str) -> Result<Move, String> {
+        let text: &str = text.trim();
+        if text.is_ascii() {
+            self.parse_move(text)
+        } else {
+            self.parse_chinese(text)
+        }
+    }
+}
+
+/// 中文纵线着法统一
+///
+/// 将棋子名、动作、前后以及数字统一成ASCII字符，并去掉空白。
+///
+/// * `text` - 中文纵线格式的着法。
+fn normalize(text: &str) -> String {
+    text.chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| {
+            NORMALIZE
+                .iter()
+                .find(|(variants, _)| variants.contains(c))
+                .map(|(_, normalized)| *normalized)
+                .unwrap_or(c)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 所有合法着法转换成中文纵线后能解析回同一个着法
+    fn assert_round_trip(fen: &str) {
+        let mut position = Position::from_fen(fen).unwrap();
+        let moves: Vec<Move> = position.legal_moves();
+        assert!(!moves.is_empty(), "{}", fen);
+        for m in moves.iter() {
+            let text: String = position.to_chinese(m);
+            assert_eq!(position.parse_chinese(&text), Ok(*m), "{} {}", fen, text);
+        }
+    }
+
+    fn chinese(position: &mut Position, iccs: &str) -> String {
+        let m: Move = position.parse_move(iccs).unwrap();
+        position.to_chinese(&m)
+    }
+
+    #[test]
+    fn chinese_round_trips_legal_moves() {
+        for fen in &[
+            START_FEN,
+            "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C2C4/9/RNBAKABNR b",
+            "5k3/9/9/9/9/9/4R4/9/4R4/3K5 w",
+            "5k3/4r4/9/4r4/9/9/9/9/9/3K5 b",
+            "3k5/9/2P6/2P6/2P6/9/9/9/9/4K4 w",
+            "3k5/9/9/2P3P2/2P3P2/9/9/9/9/4K4 w",
+            "2bak4/4a4/4b4/p1p1p3p/9/2P3p2/P3P3P/1C2B4/4A4/1NBK1A3 w",
+        ] {
+            assert_round_trip(fen);
+        }
+    }
+
+    #[test]
+    fn chinese_numbers_and_order() {
+        // 红方中文数字从右数，黑方阿拉伯数字从（黑方的）右数
+        let mut position = Position::new();
+        assert_eq!(chinese(&mut position, "h2e2"), "炮二平五");
+        assert_eq!(chinese(&mut position, "b0c2"), "马八进七");
+        let m: Move = position.parse_move("h2e2").unwrap();
+        position.make_move(m).unwrap();
+        assert_eq!(chinese(&mut position, "h9g7"), "马8进7");
+        assert_eq!(chinese(&mut position, "b7e7"), "炮2平5");
+        assert_eq!(chinese(&mut position, "a9a8"), "车1进1");
+
+        // 同一列的两个、三个棋子用前后（前中后）区分，黑方的前为靠近红方的一侧
+        let mut position = Position::from_fen("5k3/9/9/9/9/9/4R4/9/4R4/3K5 w").unwrap();
+        assert_eq!(chinese(&mut position, "e3e5"), "前车进二");
+        assert_eq!(chinese(&mut position, "e1e2"), "后车进一");
+        let mut position = Position::from_fen("5k3/4r4/9/4r4/9/9/9/9/9/3K5 b").unwrap();
+        assert_eq!(chinese(&mut position, "e6e4"), "前车进2");
+        assert_eq!(chinese(&mut position, "e8d8"), "后车平4");
+        let mut position = Position::from_fen("3k5/9/2P6/2P6/2P6/9/9/9/9/4K4 w").unwrap();
+        assert_eq!(chinese(&mut position, "c7c8"), "前兵进一");
+        assert_eq!(chinese(&mut position, "c6b6"), "中兵平八");
+        assert_eq!(chinese(&mut position, "c5b5"), "后兵平八");
+        // 两列各有两个兵时用列代替棋子名
+        let mut position = Position::from_fen("3k5/9/9/2P3P2/2P3P2/9/9/9/9/4K4 w").unwrap();
+        assert_eq!(chinese(&mut position, "c6c7"), "前七进一");
+        assert_eq!(chinese(&mut position, "g5h5"), "后三平二");
+    }
+
+    #[test]
+    fn parse_chinese_normalizes_variants() {
+        let mut position = Position::new();
+        let expected: Move = position.parse_move("h2e2").unwrap();
+        for text in &["炮二平五", "砲二平五", "炮２平５", "包 二 平 五"] {
+            assert_eq!(position.parse_chinese(text), Ok(expected), "{}", text);
+        }
+        let expected: Move = position.parse_move("b0c2").unwrap();
+        assert_eq!(position.parse_chinese("傌八進七"), Ok(expected));
+        assert_eq!(position.parse_chinese("馬八进七"), Ok(expected));
+        assert!(position.parse_chinese("炮二平").is_err());
+        assert!(position.parse_chinese("炮二进九").is_err());
+        assert_eq!(normalize("後車退二"), "rR-2");
+    }
+}
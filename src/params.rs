@@ -0,0 +1,541 @@
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::Path;
+
+use serde_json;
+use serde_yaml;
+
+use game::{HEIGHT, MAX_CELLS_SIZE, WIDTH};
+
+/**************************************************************************************************/
+/*******************************   EVALUATE DEFINITION    *****************************************/
+/**************************************************************************************************/
+// 帅 士 相 马 车 炮 兵
+const EVALUATE_BASIC: [i32; 7] = [1000000, 110, 110, 300, 600, 300, 70];
+
+// 残局的棋子价值（仕相的价值下降，马、兵的价值上升，炮的价值下降）
+const EVALUATE_BASIC_ENDGAME: [i32; 7] = [1000000, 80, 80, 320, 600, 270, 100];
+
+// 阶段值（残局为0，子力越多越接近中局）
+const EVALUATE_PHASE: [i32; 7] = [0, 0, 0, 1, 2, 1, 0];
+
+// 开局时的棋子数（阶段值的最大值的计算用）
+const START_PIECES: [i32; 7] = [2, 4, 4, 4, 4, 4, 10];
+
+const EVALUATE_KING: [i32; MAX_CELLS_SIZE] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, -9, -9, -9, 0, 0, 0,
+    0, 0, 0, -8, -8, -8, 0, 0, 0,
+    0, 0, 0, 1, 5, 1, 0, 0, 0
+];
+
+// 残局中帅（将）可以离开底线参与攻守
+const EVALUATE_KING_ENDGAME: [i32; MAX_CELLS_SIZE] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, -4, -2, -4, 0, 0, 0,
+    0, 0, 0, -2, 2, -2, 0, 0, 0,
+    0, 0, 0, 0, 3, 0, 0, 0, 0
+];
+
+const EVALUATE_ADVISER: [i32; MAX_CELLS_SIZE] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 3, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0
+];
+
+const EVALUATE_BISHOP: [i32; MAX_CELLS_SIZE] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    -2, 0, 0, 0, 3, 0, 0, 0, -2,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0
+];
+
+const EVALUATE_KNIGHT: [i32; MAX_CELLS_SIZE] = [
+    4, 8, 16, 12, 4, 12, 16, 8, 4,
+    4, 10, 28, 16, 8, 16, 28, 10, 4,
+    12, 14, 16, 20, 18, 20, 16, 14, 12,
+    8, 24, 18, 24, 20, 24, 18, 24, 8,
+    6, 16, 14, 18, 16, 18, 14, 16, 6,
+    4, 12, 16, 14, 12, 14, 16, 12, 4,
+    2, 6, 8, 6, 10, 6, 8, 6, 2,
+    4, 2, 8, 8, 4, 8, 8, 2, 4,
+    0, 2, 4, 4, -2, 4, 4, 2, 0,
+    0, -4, 0, 0, 0, 0, 0, -4, 0
+];
+
+const EVALUATE_ROOK: [i32; MAX_CELLS_SIZE] = [
+    14, 14, 12, 18, 16, 18, 12, 14, 14,
+    16, 20, 18, 24, 26, 24, 18, 20, 16,
+    12, 12, 12, 18, 18, 18, 12, 12, 12,
+    12, 18, 16, 22, 22, 22, 16, 18, 12,
+    12, 14, 12, 18, 18, 18, 12, 14, 12,
+    12, 16, 14, 20, 20, 20, 14, 16, 12,
+    6, 10, 8, 14, 14, 14, 8, 10, 6,
+    4, 8, 6, 14, 12, 14, 6, 8, 4,
+    8, 4, 8, 16, 8, 16, 8, 4, 8,
+    -2, 10, 6, 14, 12, 14, 6, 10, -2
+];
+
+const EVALUATE_CANNON: [i32; MAX_CELLS_SIZE] = [
+    6, 4, 0, -10, -12, -10, 0, 4, 6,
+    2, 2, 0, -4, -14, -4, 0, 2, 2,
+    2, 2, 0, -10, -8, -10, 0, 2, 2,
+    0, 0, -2, 4, 10, 4, -2, 0, 0,
+    0, 0, 0, 2, 8, 2, 0, 0, 0,
+    -2, 0, 4, 2, 6, 2, 4, 0, -2,
+    0, 0, 0, 2, 4, 2, 0, 0, 0,
+    4, 0, 8, 6, 10, 6, 8, 0, 4,
+    0, 2, 4, 6, 6, 6, 4, 2, 0,
+    0, 0, 2, 6, 6, 6, 2, 0, 0
+];
+
+const EVALUATE_PAWN: [i32; MAX_CELLS_SIZE] = [
+    0, 3, 6, 9, 12, 9, 6, 3, 0,
+    18, 36, 56, 80, 120, 80, 56, 36, 18,
+    14, 26, 42, 60, 80, 60, 42, 26, 14,
+    10, 20, 30, 34, 40, 34, 30, 20, 10,
+    6, 12, 18, 18, 20, 18, 18, 12, 6,
+    2, 0, 8, 0, 8, 0, 8, 0, 2,
+    0, 0, -2, 0, 4, 0, -2, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0
+];
+
+// 残局中过河兵（卒）的价值更平均，到达底线的兵（卒）价值下降
+const EVALUATE_PAWN_ENDGAME: [i32; MAX_CELLS_SIZE] = [
+    0, 2, 4, 6, 8, 6, 4, 2, 0,
+    24, 40, 56, 72, 90, 72, 56, 40, 24,
+    22, 34, 48, 60, 70, 60, 48, 34, 22,
+    20, 30, 40, 44, 48, 44, 40, 30, 20,
+    16, 22, 28, 30, 32, 30, 28, 22, 16,
+    2, 0, 8, 0, 8, 0, 8, 0, 2,
+    0, 0, -2, 0, 4, 0, -2, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0
+];
+
+// 位置分的倍率
+const EVALUATE_POSITION_WEIGHT: i32 = 8;
+
+// 机动性（每个可以到达的位置，帅 士 相 马 车 炮 兵）
+const EVALUATE_MOBILITY: [i32; 7] = [0, 0, 0, 4, 2, 1, 0];
+
+// 炮架（隔一个棋子可以吃掉的对方棋子）
+const EVALUATE_CANNON_PLATFORM: i32 = 10;
+
+// 车所在列没有兵（卒）、只有对方的兵（卒）
+const EVALUATE_ROOK_OPEN_FILE: i32 = 20;
+const EVALUATE_ROOK_HALF_OPEN_FILE: i32 = 10;
+
+// 帅（将）的安全性（中局有效）
+const EVALUATE_MISSING_ADVISER: i32 = 30;
+const EVALUATE_MISSING_BISHOP: i32 = 20;
+const EVALUATE_ROOK_ON_KING_FILE: i32 = 40;
+const EVALUATE_CENTRAL_CANNON: i32 = 30;
+const EVALUATE_KNIGHT_NEAR_PALACE: i32 = 25;
+const EVALUATE_PALACE_ATTACKER: i32 = 15;
+const EVALUATE_PALACE_DEFENDER: i32 = 5;
+const EVALUATE_KING_OPEN_FILE: i32 = 20;
+
+// 棋子价值（帅除外）、位置分以及阶段值的上限
+const MAX_PIECE_VALUE: i32 = 10000;
+const MAX_POSITION_VALUE: i32 = 1000;
+const MAX_POSITION_WEIGHT: i32 = 100;
+const MAX_PHASE_VALUE: i32 = 100;
+const MAX_ACTIVITY_VALUE: i32 = 1000;
+
+/// 评价参数
+///
+/// 中局和残局各自的棋子价值和位置分表，按阶段值（剩余子力）混合。
+/// 文件中省略的项目使用内置的默认值。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EvaluateParams {
+    /// 位置分的倍率。
+    pub position_weight: i32,
+    /// 阶段值（帅 士 相 马 车 炮 兵），开局时的合计为中局，0为残局。
+    pub phase: Vec<i32>,
+    /// 中局的评价参数。
+    pub middlegame: PhaseParams,
+    /// 残局的评价参数。
+    pub endgame: PhaseParams,
+    /// 机动性（每个可以到达的位置，帅 士 相 马 车 炮 兵）。
+    pub mobility: Vec<i32>,
+    /// 炮架（隔一个棋子可以吃掉的对方棋子）。
+    pub cannon_platform: i32,
+    /// 车所在列没有兵（卒）。
+    pub rook_open_file: i32,
+    /// 车所在列只有对方的兵（卒）。
+    pub rook_half_open_file: i32,
+    /// 帅（将）的安全性。
+    pub king_safety: KingSafetyParams,
+}
+
+/// 帅（将）的安全性评价参数
+///
+/// 九宫的防守和对方的进攻，为正数时表示扣分（防守方除外），中局有效、残局为0。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KingSafetyParams {
+    /// 缺少的仕（士），每个。
+    pub missing_adviser: i32,
+    /// 缺少的相（象），每个。
+    pub missing_bishop: i32,
+    /// 对方的车在帅（将）所在的列。
+    pub rook_on_king_file: i32,
+    /// 对方的炮在帅（将）所在的列（中炮）。
+    pub central_cannon: i32,
+    /// 对方的马在九宫附近（九宫向前两行、左右各一列以内）。
+    pub knight_near_palace: i32,
+    /// 对方的车、炮、兵（卒）在九宫以及九宫前一行，每个。
+    pub palace_attacker: i32,
+    /// 九宫中己方的棋子（帅（将）除外，加分），每个。
+    pub palace_defender: i32,
+    /// 帅（将）前方没有己方的棋子。
+    pub king_open_file: i32,
+}
+
+/// 阶段评价参数
+///
+/// 棋子价值和位置分表，位置分表为红方视角（第0个为黑方底线左端），黑方使用旋转后的位置。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PhaseParams {
+    /// 棋子价值（帅 士 相 马 车 炮 兵）。
+    pub material: Vec<i32>,
+    /// 帅（将）的位置分。
+    pub king: Vec<i32>,
+    /// 仕（士）的位置分。
+    pub adviser: Vec<i32>,
+    /// 相（象）的位置分。
+    pub bishop: Vec<i32>,
+    /// 马的位置分。
+    pub knight: Vec<i32>,
+    /// 车的位置分。
+    pub rook: Vec<i32>,
+    /// 炮的位置分。
+    pub cannon: Vec<i32>,
+    /// 兵（卒）的位置分。
+    pub pawn: Vec<i32>,
+}
+
+impl Default for EvaluateParams {
+    fn default() -> EvaluateParams {
+        EvaluateParams {
+            position_weight: EVALUATE_POSITION_WEIGHT,
+            phase: EVALUATE_PHASE.to_vec(),
+            middlegame: PhaseParams {
+                material: EVALUATE_BASIC.to_vec(),
+                king: EVALUATE_KING.to_vec(),
+                adviser: EVALUATE_ADVISER.to_vec(),
+                bishop: EVALUATE_BISHOP.to_vec(),
+                knight: EVALUATE_KNIGHT.to_vec(),
+                rook: EVALUATE_ROOK.to_vec(),
+                cannon: EVALUATE_CANNON.to_vec(),
+                pawn: EVALUATE_PAWN.to_vec(),
+            },
+            endgame: PhaseParams {
+                material: EVALUATE_BASIC_ENDGAME.to_vec(),
+                king: EVALUATE_KING_ENDGAME.to_vec(),
+                adviser: EVALUATE_ADVISER.to_vec(),
+                bishop: EVALUATE_BISHOP.to_vec(),
+                knight: EVALUATE_KNIGHT.to_vec(),
+                rook: EVALUATE_ROOK.to_vec(),
+                cannon: EVALUATE_CANNON.to_vec(),
+                pawn: EVALUATE_PAWN_ENDGAME.to_vec(),
+            },
+            mobility: EVALUATE_MOBILITY.to_vec(),
+            cannon_platform: EVALUATE_CANNON_PLATFORM,
+            rook_open_file: EVALUATE_ROOK_OPEN_FILE,
+            rook_half_open_file: EVALUATE_ROOK_HALF_OPEN_FILE,
+            king_safety: KingSafetyParams {
+                missing_adviser: EVALUATE_MISSING_ADVISER,
+                missing_bishop: EVALUATE_MISSING_BISHOP,
+                rook_on_king_file: EVALUATE_ROOK_ON_KING_FILE,
+                central_cannon: EVALUATE_CENTRAL_CANNON,
+                knight_near_palace: EVALUATE_KNIGHT_NEAR_PALACE,
+                palace_attacker: EVALUATE_PALACE_ATTACKER,
+                palace_defender: EVALUATE_PALACE_DEFENDER,
+                king_open_file: EVALUATE_KING_OPEN_FILE,
+            },
+        }
+    }
+}
+
+impl EvaluateParams {
+    /// 评价参数读取
+    ///
+    /// 读取YAML或者JSON（扩展名为.json）格式的评价参数文件并检查，格式错误或者检查失败时返回Err。
+    ///
+    /// * `path` - 评价参数文件。
+    pub fn load(path: &str) -> Result<EvaluateParams, String> {
+        let mut contents = String::new();
+        File::open(path)
+            .and_then(|mut file| file.read_to_string(&mut contents))
+            .map_err(|e| format!("unable to read [{}]: {}", path, e))?;
+        let is_json: bool = Path::new(path)
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+        let params: EvaluateParams = if is_json {
+            serde_json::from_str(&contents).map_err(|e| format!("invalid params [{}]: {}", path, e))?
+        } else {
+            serde_yaml::from_str(&contents).map_err(|e| format!("invalid params [{}]: {}", path, e))?
+        };
+        params.validate().map_err(|e| format!("invalid params [{}]: {}", path, e))?;
+        Ok(params)
+    }
+
+    /// 评价参数保存
+    ///
+    /// 以YAML或者JSON（扩展名为.json）格式写入评价参数文件，写入失败时返回Err。
+    ///
+    /// * `path` - 评价参数文件。
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let is_json: bool = Path::new(path)
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+        let contents: String = if is_json {
+            serde_json::to_string_pretty(self).map_err(|e| format!("unable to write [{}]: {}", path, e))?
+        } else {
+            serde_yaml::to_string(self).map_err(|e| format!("unable to write [{}]: {}", path, e))?
+        };
+        File::create(path)
+            .and_then(|mut file| file.write_all(contents.as_bytes()))
+            .map_err(|e| format!("unable to write [{}]: {}", path, e))
+    }
+
+    /// 评价参数检查
+    ///
+    /// 检查倍率、阶段值、中局和残局的评价参数、活动性以及帅（将）的安全性。
+    pub fn validate(&self) -> Result<(), String> {
+        if self.position_weight < 0i32 || self.position_weight > MAX_POSITION_WEIGHT {
+            return Err(format!(
+                "position_weight [{}] out of range 0..={}",
+                self.position_weight, MAX_POSITION_WEIGHT
+            ));
+        }
+        if self.phase.len() != 7usize {
+            return Err(format!("phase must have 7 values, found {}", self.phase.len()));
+        }
+        if let Some(v) = self.phase.iter().find(|v| **v < 0i32 || **v > MAX_PHASE_VALUE) {
+            return Err(format!("phase [{}] out of range 0..={}", v, MAX_PHASE_VALUE));
+        }
+        if self.total_phase() == 0i32 {
+            return Err(String::from("phase must not be all zero"));
+        }
+        if self.mobility.len() != 7usize {
+            return Err(format!("mobility must have 7 values, found {}", self.mobility.len()));
+        }
+        let safety: &KingSafetyParams = &self.king_safety;
+        let activities: [(&str, i32); 11] = [
+            ("cannon_platform", self.cannon_platform),
+            ("rook_open_file", self.rook_open_file),
+            ("rook_half_open_file", self.rook_half_open_file),
+            ("missing_adviser", safety.missing_adviser),
+            ("missing_bishop", safety.missing_bishop),
+            ("rook_on_king_file", safety.rook_on_king_file),
+            ("central_cannon", safety.central_cannon),
+            ("knight_near_palace", safety.knight_near_palace),
+            ("palace_attacker", safety.palace_attacker),
+            ("palace_defender", safety.palace_defender),
+            ("king_open_file", safety.king_open_file),
+        ];
+        for (name, v) in self.mobility.iter().map(|v| ("mobility", *v)).chain(activities.iter().cloned()) {
+            if v.abs() > MAX_ACTIVITY_VALUE {
+                return Err(format!("{} [{}] out of range", name, v));
+            }
+        }
+        self.middlegame.validate().map_err(|e| format!("middlegame: {}", e))?;
+        self.endgame.validate().map_err(|e| format!("endgame: {}", e))
+    }
+
+    /// 开局时的阶段值合计
+    pub fn total_phase(&self) -> i32 {
+        self.phase.iter().zip(START_PIECES.iter()).map(|(p, n)| p * n).sum()
+    }
+}
+
+impl PhaseParams {
+    /// 阶段评价参数检查
+    ///
+    /// 检查棋子价值的个数和范围、位置分表的大小、范围以及左右对称。
+    pub fn validate(&self) -> Result<(), String> {
+        if self.material.len() != 7usize {
+            return Err(format!("material must have 7 values, found {}", self.material.len()));
+        }
+        if self.material[0] < 0i32 {
+            return Err(String::from("material of king must not be negative"));
+        }
+        if let Some(v) = self.material[1..].iter().find(|v| **v < 0i32 || **v > MAX_PIECE_VALUE) {
+            return Err(format!("material [{}] out of range 0..={}", v, MAX_PIECE_VALUE));
+        }
+        for (name, table) in self.tables().iter() {
+            validate_table(name, table)?;
+        }
+        Ok(())
+    }
+
+    /// 位置分表取得（名称和表，帅 士 相 马 车 炮 兵）
+    pub fn tables(&self) -> [(&'static str, &Vec<i32>); 7] {
+        [
+            ("king", &self.king),
+            ("adviser", &self.adviser),
+            ("bishop", &self.bishop),
+            ("knight", &self.knight),
+            ("rook", &self.rook),
+            ("cannon", &self.cannon),
+            ("pawn", &self.pawn),
+        ]
+    }
+
+    /// 位置分表取得（可变）
+    ///
+    /// * `kind` - 棋子种类（0~6：帅 士 相 马 车 炮 兵）。
+    pub fn table_mut(&mut self, kind: usize) -> &mut Vec<i32> {
+        match kind {
+            0 => &mut self.king,
+            1 => &mut self.adviser,
+            2 => &mut self.bishop,
+            3 => &mut self.knight,
+            4 => &mut self.rook,
+            5 => &mut self.cannon,
+            _ => &mut self.pawn,
+        }
+    }
+
+    /// 位置分表取得
+    ///
+    /// * `kind` - 棋子种类（0~6：帅 士 相 马 车 炮 兵）。
+    pub fn table(&self, kind: usize) -> &[i32] {
+        match kind {
+            0 => &self.king,
+            1 => &self.adviser,
+            2 => &self.bishop,
+            3 => &self.knight,
+            4 => &self.rook,
+            5 => &self.cannon,
+            _ => &self.pawn,
+        }
+    }
+}
+
+/// 位置分表检查
+///
+/// * `name` - 表的名称。
+/// * `table` - 位置分表。
+fn validate_table(name: &str, table: &[i32]) -> Result<(), String> {
+    if table.len() != MAX_CELLS_SIZE {
+        return Err(format!("[{}] must have {} values, found {}", name, MAX_CELLS_SIZE, table.len()));
+    }
+    if let Some(v) = table.iter().find(|v| v.abs() > MAX_POSITION_VALUE) {
+        return Err(format!("[{}] value [{}] out of range", name, v));
+    }
+    for row in 0usize..HEIGHT {
+        for column in 0usize..WIDTH / 2usize {
+            if table[row * WIDTH + column] != table[row * WIDTH + WIDTH - 1usize - column] {
+                return Err(format!("[{}] must be symmetric at row {}", name, row));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+    use std::fs;
+
+    /// 写入临时文件后读取
+    fn load_text(name: &str, contents: &str) -> Result<EvaluateParams, String> {
+        let path = env::temp_dir().join(format!("chess-params-{}-{}", std::process::id(), name));
+        let path: &str = path.to_str().unwrap();
+        fs::write(path, contents).unwrap();
+        let params = EvaluateParams::load(path);
+        fs::remove_file(path).unwrap();
+        params
+    }
+
+    #[test]
+    fn resource_file_matches_defaults() {
+        assert_eq!(EvaluateParams::load("resources/evaluate.yaml"), Ok(EvaluateParams::default()));
+    }
+
+    #[test]
+    fn load_yaml_and_json() {
+        let mut params = EvaluateParams { position_weight: 3i32, ..EvaluateParams::default() };
+        params.middlegame.material[3usize] = 320i32;
+        let yaml: String = serde_yaml::to_string(&params).unwrap();
+        let json: String = serde_json::to_string(&params).unwrap();
+        assert_eq!(load_text("full.yaml", &yaml), Ok(params.clone()));
+        assert_eq!(load_text("full.json", &json), Ok(params.clone()));
+        // 扩展名决定格式
+        assert!(load_text("yaml.json", &yaml).is_err());
+
+        // 省略的项目使用默认值
+        let params = load_text("partial.yaml", "position_weight: 5\ncannon_platform: 0\n").unwrap();
+        assert_eq!(params.position_weight, 5i32);
+        assert_eq!(params.cannon_platform, 0i32);
+        assert_eq!(params.middlegame, EvaluateParams::default().middlegame);
+        let params = load_text("partial.json", r#"{"rook_open_file": 25}"#).unwrap();
+        assert_eq!(params.rook_open_file, 25i32);
+        assert_eq!(params.king_safety, EvaluateParams::default().king_safety);
+    }
+
+    #[test]
+    fn load_rejects_unknown_fields() {
+        let error: String = load_text("unknown.yaml", "position_weight: 5\nknight_bonus: 3\n").unwrap_err();
+        assert!(error.contains("knight_bonus"), "{}", error);
+        let error: String = load_text("unknown.json", r#"{"kingsafety": {}}"#).unwrap_err();
+        assert!(error.contains("kingsafety"), "{}", error);
+        // 下层的项目也不允许未知的名称
+        let mut value = serde_json::to_value(EvaluateParams::default()).unwrap();
+        value["endgame"]["queen"] = serde_json::json!([0]);
+        let error: String = load_text("nested.json", &value.to_string()).unwrap_err();
+        assert!(error.contains("queen"), "{}", error);
+    }
+
+    #[test]
+    fn load_rejects_invalid_values() {
+        let mut params = EvaluateParams::default();
+        params.middlegame.knight[1usize] += 1i32;
+        let error: String = load_text("asymmetric.yaml", &serde_yaml::to_string(&params).unwrap()).unwrap_err();
+        assert!(error.contains("middlegame: [knight] must be symmetric at row 0"), "{}", error);
+
+        let mut params = EvaluateParams::default();
+        params.endgame.pawn.pop();
+        let error: String = load_text("short.json", &serde_json::to_string(&params).unwrap()).unwrap_err();
+        assert!(error.contains("endgame: [pawn] must have 90 values"), "{}", error);
+
+        let error: String = load_text("phase.yaml", "phase: [0, 0, 0, 0, 0, 0, 0]\n").unwrap_err();
+        assert!(error.contains("phase must not be all zero"), "{}", error);
+    }
+}
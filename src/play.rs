@@ -0,0 +1,161 @@
+use std::io;
+use std::io::prelude::*;
+
+use chess::*;
+
+use cli::{EngineOptions, SearchOptions};
+
+/**************************************************************************************************/
+/*******************************     PLAY DEFINITION      *****************************************/
+/**************************************************************************************************/
+// 帅 士 相 马 车 炮 兵（红黑的马、车、炮用不同的字区分）
+const PIECES: &str = "KABNRCPkabnrcp";
+const CHINESE_GLYPHS: [char; 14] = [
+    '帅', '仕', '相', '傌', '俥', '炮', '兵', '将', '士', '象', '馬', '車', '砲', '卒',
+];
+const CHINESE_EMPTY: char = '．';
+const LATIN_EMPTY: char = '.';
+
+const RED_FILES: [char; 9] = ['九', '八', '七', '六', '五', '四', '三', '二', '一'];
+const BLACK_FILES: [char; 9] = ['１', '２', '３', '４', '５', '６', '７', '８', '９'];
+
+const HELP: &str = "moves: ICCS (h2e2) or Chinese (炮二平五, 马8进7)
+commands: undo, fen, help, quit";
+
+/// 棋子字体
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Glyphs {
+    Chinese,
+    Latin,
+}
+
+/// 人机对局
+///
+/// 在终端显示棋盘，人执`human`一方输入着法，引擎执另一方。
+/// 无合法着法的一方判负，重复局面按`rule`判定。
+///
+/// * `fen` - 开始局面。
+/// * `human` - 人执的一方。
+/// * `limit` - 引擎的检索限制。
+/// * `glyphs` - 棋子字体。
+/// * `rule` - 重复局面规则。
+/// * `engine` - 引擎的着法随机化和棋力级别。
+/// * `search` - 引擎的检索设置。
+pub fn run(
+    fen: &str,
+    human: Side,
+    limit: SearchLimit,
+    glyphs: Glyphs,
+    rule: RepetitionRule,
+    engine: EngineOptions,
+    search: SearchOptions,
+) -> Result<(), String> {
+    let mut position = Position::from_fen(fen)?;
+    position.set_rule(rule);
+    search.apply(&mut position);
+    engine.apply(&mut position);
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    println!("{}", HELP);
+
+    loop {
+        println!("{}", render(&position, glyphs));
+        let side: Side = position.side();
+        match position.status() {
+            GameStatus::Ongoing => {}
+            GameStatus::Win(winner, reason) => {
+                println!("{:?} wins ({:?})", winner, reason);
+                return Ok(());
+            }
+            GameStatus::Draw(reason) => {
+                println!("draw ({:?})", reason);
+                return Ok(());
+            }
+        }
+
+        if side != human {
+            let result: SearchResult = position.search(limit).unwrap();
+            println!(
+                "engine: {} {} (score {}, pv {})",
+                result.best_move.to_iccs(),
+                position.to_chinese(&result.best_move),
+                result.score_text(),
+                position.line_to_chinese(&result.pv).join(" ")
+            );
+            position.make_move(result.best_move)?;
+            continue;
+        }
+
+        loop {
+            print!("{:?}{}> ", side, if position.is_checked() { " (check)" } else { "" });
+            io::stdout().flush().unwrap();
+            let line: String = match lines.next() {
+                Some(Ok(l)) => l,
+                _ => return Ok(()),
+            };
+            match line.trim() {
+                "" => continue,
+                "quit" => return Ok(()),
+                "help" => println!("{}", HELP),
+                "fen" => println!("{}", position.to_fen()),
+                "undo" => {
+                    // 撤销引擎和自己的着法
+                    position.undo_move();
+                    position.undo_move();
+                    break;
+                }
+                text => match position.parse_notation(text) {
+                    Ok(m) => {
+                        println!("you: {} {}", m.to_iccs(), position.to_chinese(&m));
+                        position.make_move(m)?;
+                        break;
+                    }
+                    Err(message) => println!("{}", message),
+                },
+            }
+        }
+    }
+}
+
+/// 棋盘显示
+///
+/// 红方在下，左侧为ICCS的行，上下为ICCS的列；中文字体时另外显示双方的纵线编号。
+///
+/// * `position` - 局面。
+/// * `glyphs` - 棋子字体。
+pub fn render(position: &Position, glyphs: Glyphs) -> String {
+    let mut text = String::new();
+    let files: String = "abcdefghi".chars().map(|c| format!("{} ", c)).collect();
+    text.push_str(&format!("   {}\n", files));
+    if glyphs == Glyphs::Chinese {
+        text.push_str(&format!("   {}\n", BLACK_FILES.iter().collect::<String>()));
+    }
+    for row in 0usize..HEIGHT {
+        if row == HEIGHT / 2usize {
+            text.push_str(match glyphs {
+                Glyphs::Chinese => "   　　楚河　　　汉界\n",
+                Glyphs::Latin => "   ~~~~~~~~~~~~~~~~~\n",
+            });
+        }
+        text.push_str(&format!("{}  ", HEIGHT - 1usize - row));
+        for column in 0usize..WIDTH {
+            let piece: Option<char> = position.piece_at(row * WIDTH + column);
+            match glyphs {
+                Glyphs::Chinese => text.push(match piece {
+                    Some(p) => CHINESE_GLYPHS[PIECES.find(p).unwrap()],
+                    None => CHINESE_EMPTY,
+                }),
+                Glyphs::Latin => {
+                    text.push(piece.unwrap_or(LATIN_EMPTY));
+                    text.push(' ');
+                }
+            }
+        }
+        text.push('\n');
+    }
+    if glyphs == Glyphs::Chinese {
+        text.push_str(&format!("   {}\n", RED_FILES.iter().collect::<String>()));
+    }
+    text.push_str(&format!("   {}", files));
+    text
+}
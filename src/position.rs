@@ -0,0 +1,657 @@
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+use game::*;
+use params::EvaluateParams;
+use tt::TranspositionTable;
+
+/**************************************************************************************************/
+/*******************************   POSITION DEFINITION    *****************************************/
+/**************************************************************************************************/
+// FEN中可以使用的棋子
+const FEN_PIECES: &str = "KABNRCPkabnrcp";
+
+/// 检索结果
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    /// 最佳着法。
+    pub best_move: Move,
+    /// 评价值（走棋方视角，正数为走棋方有利）。
+    pub score: i32,
+    /// 访问的节点数。
+    pub nodes: u64,
+    /// 完成的检索深度。
+    pub depth: usize,
+    /// 主要变例（从最佳着法开始）。
+    pub pv: Vec<Move>,
+}
+
+impl SearchResult {
+    /// 将死步数取得
+    ///
+    /// 评价值为将死时返回到将死为止的回合数，走棋方将死对方时为正数，被将死时为负数。
+    pub fn mate(&self) -> Option<i32> {
+        if self.score > MATE_BOUND {
+            Some((MATE_VALUE - self.score + 1i32) / 2i32)
+        } else if self.score < -MATE_BOUND {
+            Some(-(MATE_VALUE + self.score) / 2i32)
+        } else {
+            None
+        }
+    }
+
+    /// 主要变例的ICCS格式表示
+    ///
+    /// 各着法以空格分隔。
+    pub fn pv_iccs(&self) -> String {
+        self.pv.iter().map(|m| m.to_iccs()).collect::<Vec<String>>().join(" ")
+    }
+
+    /// 评价值表示
+    ///
+    /// 将死时为`mate N`，否则为评价值。
+    pub fn score_text(&self) -> String {
+        match self.mate() {
+            Some(n) => format!("mate {}", n),
+            None => self.score.to_string(),
+        }
+    }
+}
+
+/// 对局结束的理由
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Reason {
+    /// 无合法着法（被将死或者困毙）。
+    NoLegalMoves,
+    /// 长将判负。
+    PerpetualCheck,
+    /// 长捉判负。
+    PerpetualChase,
+    /// 重复局面判和。
+    Repetition,
+    /// 自然限着（60回合没有吃子）判和。
+    MoveLimit,
+    /// 双方子力不足判和。
+    InsufficientMaterial,
+}
+
+/// 对局状态
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameStatus {
+    /// 对局中。
+    Ongoing,
+    /// 指定方胜。
+    Win(Side, Reason),
+    /// 和棋。
+    Draw(Reason),
+}
+
+/// 局面
+///
+/// 棋盘、走棋方以及着法历史。所有着法都经过合法性检查。
+pub struct Position {
+    board: Board,
+}
+
+impl Position {
+    /// 初始局面
+    ///
+    /// 新建红方先走的初始局面。
+    pub fn new() -> Position {
+        Position::from_fen(START_FEN).unwrap()
+    }
+
+    /// FEN解析
+    ///
+    /// 根据FEN新建局面，棋盘必须为10行9列，双方各有一个帅（将），帅（将）、士（仕）、相（象）、兵（卒）在规定的位置。
+    /// 第二个字段为走棋方（w或r为红方，b为黑方），省略时为黑方。
+    /// 第五个字段为没有吃子的步数（自然限着用），省略时为0。
+    ///
+    /// * `fen` - FEN字符串。
+    pub fn from_fen(fen: &str) -> Result<Position, String> {
+        validate_fen(fen)?;
+        let mut board = Board::new();
+        board.init_board(fen);
+        Ok(Position { board })
+    }
+
+    /// FEN取得
+    ///
+    /// 将局面转换成FEN字符串（包含走棋方），没有吃子的步数不为0时追加第5个字段。
+    pub fn to_fen(&self) -> String {
+        self.board.to_fen()
+    }
+
+    /// 走棋方取得
+    pub fn side(&self) -> Side {
+        self.board.side()
+    }
+
+    /// 棋子取得
+    ///
+    /// 返回指定位置的棋子（FEN字符，大写为红方），超出棋盘时返回None。
+    ///
+    /// * `square` - 位置（0~89），0为黑方底线左端（ICCS的a9）。
+    pub fn piece_at(&self, square: usize) -> Option<char> {
+        if square < MAX_CELLS_SIZE {
+            self.board.get_piece(square)
+        } else {
+            None
+        }
+    }
+
+    /// 合法着法生成
+    ///
+    /// 生成走棋方的所有合法着法。
+    pub fn legal_moves(&mut self) -> Vec<Move> {
+        let side: Side = self.board.side();
+        self.board.generate_legal_moves(&side)
+    }
+
+    /// 将军检测
+    ///
+    /// 检测走棋方是否被将军。
+    pub fn is_checked(&mut self) -> bool {
+        let side: Side = self.board.side();
+        self.board.is_checked(&side)
+    }
+
+    /// 着法解析
+    ///
+    /// 将ICCS格式的着法（例：h2e2）解析成合法着法，格式错误或者不合法时返回Err。
+    ///
+    /// * `iccs` - ICCS格式的着法。
+    pub fn parse_move(&mut self, iccs: &str) -> Result<Move, String> {
+        let (from, to) = self
+            .board
+            .parse_iccs(iccs)
+            .ok_or_else(|| format!("invalid move [{}]", iccs))?;
+        self.legal_moves()
+            .into_iter()
+            .find(|m| m.from == from && m.to == to)
+            .ok_or_else(|| format!("illegal move [{}]", iccs))
+    }
+
+    /// 着法执行
+    ///
+    /// 执行合法着法并交换走棋方，不合法时返回Err。
+    ///
+    /// * `m` - 着法。
+    pub fn make_move(&mut self, m: Move) -> Result<(), String> {
+        if !self.legal_moves().contains(&m) {
+            return Err(format!("illegal move [{}]", m.to_iccs()));
+        }
+        self.board.make_move(m.from, m.to);
+        Ok(())
+    }
+
+    /// 着法撤销
+    ///
+    /// 撤销最后一步着法，没有历史时返回None。
+    pub fn undo_move(&mut self) -> Option<Move> {
+        self.board.unmake_move()
+    }
+
+    /// 着法历史取得
+    pub fn history(&self) -> Vec<Move> {
+        self.board.history()
+    }
+
+    /// 没有吃子的步数取得
+    ///
+    /// 自然限着用的计数，FEN的第5个字段为开始局面的步数。
+    pub fn no_capture_plies(&self) -> usize {
+        self.board.no_capture_plies()
+    }
+
+    /// 局面键值取得
+    ///
+    /// 返回棋子配置的Zobrist键值，相同棋子配置的局面键值相同。
+    pub fn key(&self) -> u64 {
+        self.board.key()
+    }
+
+    /// 重复局面规则设置
+    ///
+    /// 设置长将、长捉的判定规则，默认为亚洲规则。
+    ///
+    /// * `rule` - 重复局面规则。
+    pub fn set_rule(&mut self, rule: RepetitionRule) {
+        self.board.set_rule(rule);
+    }
+
+    /// 着法随机化设置
+    ///
+    /// 评价值与最佳着法相差`margin`以内的着法中随机选择，`margin`为0时不随机（默认）。
+    ///
+    /// * `randomness` - 着法随机化。
+    pub fn set_randomness(&mut self, randomness: Randomness) {
+        self.board.set_randomness(randomness);
+    }
+
+    /// 棋力级别设置
+    ///
+    /// 限制检索深度和节点数、加入评价噪声并在相差不大的着法中随机选择，`MAX_SKILL_LEVEL`为不限制。
+    ///
+    /// * `skill` - 棋力级别。
+    pub fn set_skill(&mut self, skill: Skill) {
+        self.board.set_skill(skill);
+    }
+
+    /// 检索线程数设置
+    ///
+    /// 2以上时并行检索（Lazy SMP），默认为1。
+    ///
+    /// * `threads` - 线程数（1~`MAX_SEARCH_THREADS`）。
+    pub fn set_threads(&mut self, threads: usize) {
+        self.board.set_threads(threads);
+    }
+
+    /// 置换表大小设置
+    ///
+    /// 大小改变时下一次检索重新分配置换表（默认为`DEFAULT_HASH_SIZE`）。
+    ///
+    /// * `size` - 大小（MB，1~`MAX_HASH_SIZE`）。
+    pub fn set_hash_size(&mut self, size: usize) {
+        self.board.set_hash_size(size);
+    }
+
+    /// 置换表设置
+    ///
+    /// 多个局面共用同一个置换表，检索之间保留记录并避免每个局面各自分配。
+    ///
+    /// * `table` - 置换表。
+    pub fn set_transposition_table(&mut self, table: Arc<TranspositionTable>) {
+        self.board.set_table(table);
+    }
+
+    /// 选择性检索设置
+    ///
+    /// 空着剪枝、后期着法减少深度、无益剪枝的开关（默认全部有效）。
+    ///
+    /// * `selectivity` - 选择性检索。
+    pub fn set_selectivity(&mut self, selectivity: Selectivity) {
+        self.board.set_selectivity(selectivity);
+    }
+
+    /// 评价参数设置
+    ///
+    /// 替换该局面使用的评价参数（默认为内置的默认值）。
+    ///
+    /// * `params` - 评价参数。
+    pub fn set_evaluate_params(&mut self, params: Arc<EvaluateParams>) {
+        self.board.set_params(params);
+    }
+
+    /// 评价值取得
+    ///
+    /// 返回不检索时的静态评价值（黑方视角，与`evaluate_trace`的`score`相同）。
+    pub fn evaluate(&self) -> i32 {
+        self.board.evaluate()
+    }
+
+    /// 评价明细取得
+    ///
+    /// 返回双方每个棋子的棋子价值、位置分、活动性以及帅（将）的安全性等评价项目。
+    pub fn evaluate_trace(&self) -> EvaluateTrace {
+        self.board.evaluate_trace()
+    }
+
+    /// 对局状态取得
+    ///
+    /// 无合法着法的一方判负；同一局面第三次出现时按规则判定长将、长捉的一方判负，否则判和（检索中第二次出现就判定）。
+    /// 60回合没有吃子或者双方都没有马、车、炮、兵时判和。
+    pub fn status(&mut self) -> GameStatus {
+        let side: Side = self.board.side();
+        if self.legal_moves().is_empty() {
+            return GameStatus::Win(side.opponent(), Reason::NoLegalMoves);
+        }
+        match self.board.game_repetition() {
+            None if self.board.is_move_limit() => GameStatus::Draw(Reason::MoveLimit),
+            None if self.board.is_insufficient_material() => {
+                GameStatus::Draw(Reason::InsufficientMaterial)
+            }
+            None => GameStatus::Ongoing,
+            Some(RepetitionResult::Draw) => GameStatus::Draw(Reason::Repetition),
+            Some(RepetitionResult::Loss(loser, kind)) => GameStatus::Win(
+                loser.opponent(),
+                if kind == RepetitionKind::Check {
+                    Reason::PerpetualCheck
+                } else {
+                    Reason::PerpetualChase
+                },
+            ),
+        }
+    }
+
+    /// 默认检索限制
+    ///
+    /// 根据棋子数决定的固定深度。
+    pub fn default_limit(&self) -> SearchLimit {
+        SearchLimit::depth(self.board.default_depth())
+    }
+
+    /// 检索
+    ///
+    /// 按检索限制检索走棋方的最佳着法，无合法着法时返回None。
+    ///
+    /// * `limit` - 检索限制。
+    pub fn search(&mut self, limit: SearchLimit) -> Option<SearchResult> {
+        let best_move: Move = self.board.search_by_limit(limit)?;
+        Some(self.result(best_move))
+    }
+
+    /// 准确值检索
+    ///
+    /// 不使用渴望窗口，根节点所有着法的评价值都是准确值（之后用`multipv`取得多变例），无合法着法时返回None。
+    ///
+    /// * `limit` - 检索限制。
+    pub fn search_exact(&mut self, limit: SearchLimit) -> Option<SearchResult> {
+        let selectivity: Selectivity = self.board.selectivity();
+        self.board.set_selectivity(Selectivity { aspiration: false, ..selectivity });
+        let best_move: Option<Move> = self.board.search_by_limit(limit);
+        self.board.set_selectivity(selectivity);
+        Some(self.result(best_move?))
+    }
+
+    /// 检索结果生成
+    ///
+    /// * `best_move` - 最佳着法。
+    fn result(&self, best_move: Move) -> SearchResult {
+        SearchResult {
+            best_move,
+            score: self.board.score(),
+            nodes: self.board.nodes(),
+            depth: self.board.depth(),
+            pv: self.board.principal_variation().to_vec(),
+        }
+    }
+
+    /// 停止标志取得
+    ///
+    /// 其它线程设置该标志（`store(true)`）后检索中止，使用最后完成一层的结果。
+    /// 标志不会自动清除，开始下一次检索之前由调用方清除。
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        self.board.halt_flag()
+    }
+
+    /// 多变例检索
+    ///
+    /// 按检索限制检索，返回评价值最高的`count`个根节点着法的检索结果（评价值从高到低），
+    /// 无合法着法时返回空列表。`count`大于1时需要所有着法的准确值，不使用渴望窗口。
+    ///
+    /// * `limit` - 检索限制。
+    /// * `count` - 变例数（最小为1）。
+    pub fn search_multipv(&mut self, limit: SearchLimit, count: usize) -> Vec<SearchResult> {
+        let found: Option<SearchResult> = if count > 1usize { self.search_exact(limit) } else { self.search(limit) };
+        match found {
+            Some(_) => self.multipv(count),
+            None => Vec::new(),
+        }
+    }
+
+    /// 渴望窗口的检索失败取得
+    ///
+    /// 返回上一次检索中渴望窗口的所有失败（fail high/fail low，按发生的顺序）。
+    pub fn aspiration_fails(&self) -> &[AspirationFail] {
+        self.board.aspiration_fails()
+    }
+
+    /// 多变例取得
+    ///
+    /// 返回上一次检索中评价值最高的`count`个根节点着法的检索结果（评价值从高到低）。
+    /// 结果只按评价值排序，不受着法随机化的影响。
+    ///
+    /// * `count` - 变例数（最小为1）。
+    pub fn multipv(&self, count: usize) -> Vec<SearchResult> {
+        let nodes: u64 = self.board.nodes();
+        let depth: usize = self.board.depth();
+        self.board
+            .root_moves()
+            .iter()
+            .take(count.max(1usize))
+            .map(|root| SearchResult { best_move: root.node, score: root.score, nodes, depth, pv: root.pv.clone() })
+            .collect()
+    }
+
+    /// 走法数统计
+    ///
+    /// 统计指定深度的合法着法叶子节点数。
+    ///
+    /// * `depth` - 深度。
+    pub fn perft(&mut self, depth: usize) -> u64 {
+        self.board.perft(depth)
+    }
+}
+
+impl Default for Position {
+    fn default() -> Position {
+        Position::new()
+    }
+}
+
+/// 棋子位置检查
+///
+/// 帅（将）、士（仕）只能在九宫的规定位置，相（象）只能在己方的七个位置，
+/// 兵（卒）不能在己方的兵线之后，过河之前只能在五条兵线的纵线上。其它棋子不检查。
+///
+/// * `piece` - 棋子。
+/// * `row` - 行（0~9，黑方底线为0）。
+/// * `column` - 列（0~8）。
+fn is_legal_square(piece: char, row: usize, column: usize) -> bool {
+    // 转换成红方视角的行（己方底线为9），各位置左右对称
+    let row: usize = if piece.is_ascii_uppercase() { row } else { 9usize - row };
+    match piece.to_ascii_uppercase() {
+        'K' => row >= 7usize && (3usize..=5usize).contains(&column),
+        'A' => matches!((row, column), (9, 3) | (9, 5) | (8, 4) | (7, 3) | (7, 5)),
+        'B' => matches!((row, column), (9, 2) | (9, 6) | (7, 0) | (7, 4) | (7, 8) | (5, 2) | (5, 6)),
+        'P' => row <= 4usize || row <= 6usize && column.is_multiple_of(2usize),
+        _ => true,
+    }
+}
+
+/// FEN检查
+///
+/// 检查棋盘的行列数、棋子字符、棋子的位置、帅（将）的数量、走棋方以及没有吃子的步数。
+///
+/// * `fen` - FEN字符串。
+fn validate_fen(fen: &str) -> Result<(), String> {
+    let mut fields = fen.split_whitespace();
+    let placement: &str = fields.next().ok_or_else(|| String::from("empty fen"))?;
+    let ranks: Vec<&str> = placement.split('/').collect();
+    if ranks.len() != 10usize {
+        return Err(format!("fen must have 10 ranks [{}]", placement));
+    }
+    for (row, rank) in ranks.iter().enumerate() {
+        let mut columns = 0usize;
+        for c in rank.chars() {
+            match c {
+                '1'..='9' => columns += c.to_digit(10).unwrap() as usize,
+                _ if FEN_PIECES.contains(c) => {
+                    if columns < 9usize && !is_legal_square(c, row, columns) {
+                        return Err(format!(
+                            "piece [{}] on illegal square [{}{}]",
+                            c,
+                            (b'a' + columns as u8) as char,
+                            9usize - row
+                        ));
+                    }
+                    columns += 1usize;
+                }
+                _ => return Err(format!("invalid piece [{}]", c)),
+            }
+        }
+        if columns != 9usize {
+            return Err(format!("rank must have 9 columns [{}]", rank));
+        }
+    }
+    for king in &['K', 'k'] {
+        if placement.matches(*king).count() != 1usize {
+            return Err(format!("fen must have exactly one [{}]", king));
+        }
+    }
+    match fields.next() {
+        None | Some("w") | Some("r") | Some("b") => {}
+        Some(side) => return Err(format!("invalid side [{}]", side)),
+    }
+    match fields.nth(2) {
+        Some(plies) if plies.parse::<usize>().is_err() => {
+            Err(format!("invalid no-capture plies [{}]", plies))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_fen_rejects_illegal_squares() {
+        for fen in &[
+            // 帅出九宫
+            "4k4/9/9/9/9/9/9/9/9/K8 w",
+            // 相不在规定位置
+            "4k4/9/9/9/9/9/9/9/9/4K3B w",
+            // 士不在规定位置
+            "4k4/9/9/9/9/9/9/9/3A5/3K5 w",
+            // 将出九宫，象过河
+            "9/9/9/9/9/9/9/9/3k5/4K4 b",
+            "4k4/9/9/9/9/2b6/9/9/9/4K4 b",
+            // 兵在兵线之后，未过河的兵不在兵的纵线上
+            "4k4/9/9/9/9/9/9/P8/9/4K4 w",
+            "4k4/9/9/9/9/9/1P7/9/9/4K4 w",
+            "4k4/9/9/1p7/9/9/9/9/9/4K4 b",
+        ] {
+            assert!(Position::from_fen(fen).is_err(), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn from_fen_accepts_legal_squares() {
+        for fen in &[
+            START_FEN,
+            "3ak4/4a4/4b4/9/2b3P2/7p1/6P2/B8/4A4/3AK1B2 w",
+            "4k4/9/9/9/9/9/9/9/9/3K4R w",
+            "4k4/P8/9/9/4p4/9/9/9/8p/4K4 w",
+        ] {
+            assert!(Position::from_fen(fen).is_ok(), "{}", fen);
+        }
+        let mut position = Position::from_fen("4k4/9/9/9/9/9/9/9/9/3K4R w").unwrap();
+        assert!(position.search(SearchLimit::depth(2usize)).is_some());
+    }
+
+    #[test]
+    fn fen_keeps_no_capture_plies() {
+        let mut position = Position::from_fen("4k4/9/9/9/9/9/9/9/9/R2K5 w - - 37 20").unwrap();
+        assert_eq!(position.no_capture_plies(), 37usize);
+        assert_eq!(position.to_fen(), "4k4/9/9/9/9/9/9/9/9/R2K5 w - - 37");
+        let m: Move = position.parse_move("a0a1").unwrap();
+        position.make_move(m).unwrap();
+        assert_eq!(position.no_capture_plies(), 38usize);
+        let restored = Position::from_fen(&position.to_fen()).unwrap();
+        assert_eq!(restored.no_capture_plies(), 38usize);
+        assert_eq!(restored.to_fen(), position.to_fen());
+        // 省略时为0，FEN中也省略
+        let position = Position::from_fen("4k4/9/9/9/9/9/9/9/9/R2K5 w").unwrap();
+        assert_eq!(position.no_capture_plies(), 0usize);
+        assert_eq!(position.to_fen(), "4k4/9/9/9/9/9/9/9/9/R2K5 w");
+    }
+
+    #[test]
+    fn move_limit_draws_after_120_plies() {
+        let fen: &str = "4k4/9/9/9/9/9/9/9/9/R2K5 w - - 118";
+        let statuses: Vec<GameStatus> = play(fen, &["a0a1", "e9f9"]);
+        assert_eq!(statuses[0], GameStatus::Ongoing);
+        assert_eq!(statuses[1], GameStatus::Draw(Reason::MoveLimit));
+        // 吃子后重新计数
+        let fen: &str = "4k4/9/9/9/9/9/9/9/r8/R2K5 w - - 119";
+        let statuses: Vec<GameStatus> = play(fen, &["a0a1"]);
+        assert_eq!(statuses[0], GameStatus::Ongoing);
+    }
+
+    #[test]
+    fn insufficient_material_draws() {
+        for fen in &[
+            "4k4/9/9/9/9/9/9/9/9/4K4 w",
+            "2ba1ab2/3k5/9/9/9/9/9/9/9/2BAKAB2 w",
+        ] {
+            let mut position = Position::from_fen(fen).unwrap();
+            assert_eq!(position.status(), GameStatus::Draw(Reason::InsufficientMaterial), "{}", fen);
+        }
+        // 任何一方还有马、车、炮、兵时继续
+        for fen in &[
+            "3k5/9/9/9/9/9/9/9/9/4K1N2 w",
+            "2ba1ab2/3k5/9/9/9/9/9/9/9/1CBAKAB2 w",
+            "3k5/9/9/9/9/9/6P2/9/9/4K4 w",
+            "2bakab2/9/4r4/9/9/9/9/9/9/2BAK4 w",
+        ] {
+            let mut position = Position::from_fen(fen).unwrap();
+            assert_eq!(position.status(), GameStatus::Ongoing, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn mate_scores_count_moves_from_side_to_move() {
+        // 红方一步杀
+        let mut position = Position::from_fen("4k4/R8/9/9/9/9/9/9/9/3K4R w").unwrap();
+        let result: SearchResult = position.search(SearchLimit::depth(3usize)).unwrap();
+        assert_eq!(result.best_move.to_iccs(), "i0i9");
+        assert_eq!(result.score, MATE_VALUE - 1i32);
+        assert_eq!(result.mate(), Some(1i32));
+        assert_eq!(result.score_text(), "mate 1");
+
+        // 黑方走一步后被杀（2步）
+        let mut position = Position::from_fen("4k4/R8/9/9/9/9/9/9/9/3K4R b").unwrap();
+        let result: SearchResult = position.search(SearchLimit::depth(3usize)).unwrap();
+        assert_eq!(result.best_move.to_iccs(), "e9f9");
+        assert_eq!(result.score, -(MATE_VALUE - 2i32));
+        assert_eq!(result.mate(), Some(-1i32));
+        assert_eq!(result.score_text(), "mate -1");
+
+        // 红方两步杀（3步）
+        let mut position = Position::from_fen("4k4/9/9/9/9/9/9/9/R8/3K4R w").unwrap();
+        let result: SearchResult = position.search(SearchLimit::depth(4usize)).unwrap();
+        assert_eq!(result.score, MATE_VALUE - 3i32);
+        assert_eq!(result.mate(), Some(2i32));
+
+        // 普通的评价值
+        let result = SearchResult { score: 35i32, ..result };
+        assert_eq!(result.mate(), None);
+        assert_eq!(result.score_text(), "35");
+    }
+
+    /// 依次走出着法，返回每一步之后的对局状态
+    fn play(fen: &str, moves: &[&str]) -> Vec<GameStatus> {
+        let mut position = Position::from_fen(fen).unwrap();
+        moves
+            .iter()
+            .map(|iccs| {
+                let m: Move = position.parse_move(iccs).unwrap();
+                position.make_move(m).unwrap();
+                position.status()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn perpetual_check_loses_on_third_occurrence() {
+        let moves = [
+            "a0a8", "e8e9", "a8a9", "e9e8", "a9a8", "e8e9", "a8a9", "e9e8", "a9a8",
+        ];
+        let statuses: Vec<GameStatus> = play("9/4k4/9/9/9/9/9/9/9/R2K5 w", &moves);
+        // 第5步之后同一局面第二次出现，对局继续
+        assert!(statuses[..8].iter().all(|s| *s == GameStatus::Ongoing));
+        assert_eq!(statuses[8], GameStatus::Win(Side::Black, Reason::PerpetualCheck));
+    }
+
+    #[test]
+    fn idle_repetition_draws_on_third_occurrence() {
+        let moves = [
+            "a0a1", "i9i8", "a1a0", "i8i9", "a0a1", "i9i8", "a1a0", "i8i9",
+        ];
+        let statuses: Vec<GameStatus> = play("5k2r/9/9/9/9/9/9/9/9/R2K5 w", &moves);
+        assert!(statuses[..7].iter().all(|s| *s == GameStatus::Ongoing));
+        assert_eq!(statuses[7], GameStatus::Draw(Reason::Repetition));
+    }
+}
@@ -1,22 +1,64 @@
 use std::io::{Read, Write};
 use std::net::{TcpListener, TcpStream};
-use std::thread;
 use serde_yaml;
 use std::fs::File;
-use std::io::prelude::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use game::*;
+use chess::*;
 use std::borrow::Cow;
 
+use cli::SearchOptions;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Config {
     pub server_ip: String,
     pub server_port: i32,
+    #[serde(default)]
+    pub evaluate_file: Option<String>,
+    /// 着法随机化的评价值范围（省略时不随机）。
+    #[serde(default)]
+    pub random_margin: Option<i32>,
+    /// 着法随机化的种子（省略时使用启动时间），每个请求使用不同的种子。
+    #[serde(default)]
+    pub random_seed: Option<u64>,
+    /// 棋力级别（0~10，省略时为最强），请求中指定时优先。
+    #[serde(default)]
+    pub skill: Option<usize>,
+    /// 检索线程数（省略时为1），命令行指定时优先。
+    #[serde(default)]
+    pub threads: Option<usize>,
+    /// 置换表大小（MB，省略时为16），命令行指定时优先。
+    #[serde(default)]
+    pub hash: Option<usize>,
+    /// 选择性检索（all、none或者逗号分隔的null、lmr、futility、ext、pvs、asp，省略时为all），命令行指定时优先。
+    #[serde(default)]
+    pub pruning: Option<String>,
+    /// 应答中是否附加检索信息（深度、评价值、节点数、主要变例），省略时只应答着法。
+    #[serde(default)]
+    pub response_pv: bool,
+    /// 应答后以预想的对方着法后台思考（只在没有棋力级别和着法随机化时），省略时为false。
+    #[serde(default)]
+    pub ponder: bool,
 }
 
 pub struct Server {
     config: Config,
-    board: Board,
+    search: SearchOptions,
+    /// 所有请求共用的置换表。
+    table: Arc<TranspositionTable>,
+    /// 上一个请求是否使用了棋力级别（置换表中有带噪声的评价值）。
+    noisy: bool,
+    requests: u64,
+    ponder: Option<Ponder>,
+}
+
+/// 后台思考中的线程
+struct Ponder {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<Position>,
 }
 
 impl Server {
@@ -28,11 +70,32 @@ impl Server {
                 file.read_to_string(&mut contents).expect("Unable to read file");
                 serde_yaml::from_str(&contents).unwrap()
             },
-            board: Board::new(),
+            search: SearchOptions::default(),
+            table: Arc::new(TranspositionTable::with_size(1usize)),
+            noisy: false,
+            requests: 0u64,
+            ponder: None,
         }
     }
 
-    pub fn startup(&mut self) {
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// 服务器启动
+    ///
+    /// * `search` - 检索设置（命令行优先于配置文件）。
+    pub fn startup(&mut self, search: SearchOptions) {
+        self.table = Arc::new(TranspositionTable::with_size(search.hash));
+        self.search = search;
+        if (self.config.random_margin.is_some() || self.config.skill.is_some()) && self.config.random_seed.is_none() {
+            self.config.random_seed = Some(
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_nanos() as u64)
+                    .unwrap_or(0u64),
+            );
+        }
         let ip = format!("{}:{}", self.config.server_ip, self.config.server_port);
         let listener = TcpListener::bind(ip).unwrap();
         println!("listening started, ready to accept");
@@ -44,19 +107,203 @@ impl Server {
     }
 
     fn handle_connection(&mut self, mut stream: TcpStream) {
-        let mut buffer = [0; 100];
-        stream.read(&mut buffer).unwrap();
-        let message: Cow<str> = String::from_utf8_lossy(&buffer[..]);
+        let mut buffer = [0; 256];
+        let size: usize = stream.read(&mut buffer).unwrap();
+        let message: Cow<str> = String::from_utf8_lossy(&buffer[..size]);
         let chess_board: &str = message.trim_end_matches('\u{0}');
 
         println!("input=[{}]", chess_board);
-        self.board.init_board(chess_board);
-
-        let response: String = self.board.search();
+        let (response, next) = self.respond(chess_board);
         println!("result=[{}]", response);
 
-        stream.write(response.as_bytes()).unwrap();
+        stream.write_all(response.as_bytes()).unwrap();
         stream.flush().unwrap();
+        if let Some((position, pv)) = next {
+            self.start_ponder(position, &pv);
+        }
+    }
+
+    /// 请求应答
+    ///
+    /// 请求为`<FEN>[ skill <级别>]`，检索后返回应答以及后台思考用的局面和主要变例（不思考时为None）。
+    ///
+    /// * `chess_board` - 请求。
+    fn respond(&mut self, chess_board: &str) -> (String, Option<(Position, Vec<Move>)>) {
+        let (chess_board, skill) = match chess_board.split_once(" skill ") {
+            Some((fen, level)) => (fen, level.trim().parse::<usize>().ok()),
+            None => (chess_board, self.config.skill),
+        };
+        let pondered: Option<Position> = self.stop_ponder();
+        let mut next: Option<(Position, Vec<Move>)> = None;
+        let response: String = match Position::from_fen(chess_board) {
+            Ok(mut position) => {
+                self.search.apply(&mut position);
+                // 棋力级别的评价噪声每个请求不同，使用前后清除共用的置换表
+                if skill.is_some() || self.noisy {
+                    self.table.clear();
+                }
+                self.noisy = skill.is_some();
+                position.set_transposition_table(self.table.clone());
+                let seed: u64 = self.config.random_seed.unwrap_or(0u64).wrapping_add(self.requests);
+                if let Some(level) = skill {
+                    position.set_skill(Skill { level, seed });
+                }
+                if let Some(margin) = self.config.random_margin {
+                    position.set_randomness(Randomness { margin, seed });
+                }
+                self.requests += 1u64;
+                let limit: SearchLimit = position.default_limit();
+                let plain: bool = skill.is_none() && self.config.random_margin.is_none();
+                // 预想的对方着法命中时使用后台思考的局面（结果或者置换表）
+                let result: Option<SearchResult> = match pondered {
+                    Some(ponder)
+                        if plain && ponder.key() == position.key() && ponder.side() == position.side() =>
+                    {
+                        let reused: Option<SearchResult> = ponder
+                            .multipv(1usize)
+                            .into_iter()
+                            .next()
+                            .filter(|r| limit.depth.is_some_and(|depth| r.depth >= depth));
+                        println!("ponder---hit=[true],reused=[{}]", reused.is_some());
+                        position = ponder;
+                        // 停止后台思考时设置的停止标志清除后再检索
+                        reused.or_else(|| {
+                            position.stop_flag().store(false, Ordering::Relaxed);
+                            position.search(limit)
+                        })
+                    }
+                    _ => position.search(limit),
+                };
+                // 渴望窗口的失败（fail high/fail low）
+                for fail in position.aspiration_fails() {
+                    println!(
+                        "aspiration---depth=[{}],fail=[{}],score=[{}],window=[{},{}]",
+                        fail.depth,
+                        if fail.high { "high" } else { "low" },
+                        fail.score,
+                        fail.window.0,
+                        fail.window.1
+                    );
+                }
+                match result {
+                    Some(result) => {
+                        let info: String = format!(
+                            "info---depth=[{}],score=[{}],nodes=[{}],pv=[{}],pv_chinese=[{}]",
+                            result.depth,
+                            result.score_text(),
+                            result.nodes,
+                            result.pv_iccs(),
+                            position.line_to_chinese(&result.pv).join(" ")
+                        );
+                        println!("{}", info);
+                        if self.config.ponder && plain {
+                            next = Some((position, result.pv.clone()));
+                        }
+                        if self.config.response_pv {
+                            format!("{}\n{}", result.best_move.to_coordinate(), info)
+                        } else {
+                            result.best_move.to_coordinate()
+                        }
+                    }
+                    None => String::new(),
+                }
+            }
+            Err(message) => {
+                println!("error=[{}]", message);
+                String::new()
+            }
+        };
+        (response, next)
+    }
+
+    /// 后台思考开始
+    ///
+    /// 走出主要变例的前两步（自己的着法和预想的对方着法），在其它线程无限检索，直到下一个请求。
+    ///
+    /// * `position` - 检索过的局面。
+    /// * `pv` - 主要变例。
+    fn start_ponder(&mut self, mut position: Position, pv: &[Move]) {
+        if pv.len() < 2usize || position.make_move(pv[0]).is_err() || position.make_move(pv[1]).is_err() {
+            return;
+        }
+        println!("ponder---move=[{}],reply=[{}]", pv[0].to_coordinate(), pv[1].to_coordinate());
+        let stop: Arc<AtomicBool> = position.stop_flag();
+        stop.store(false, Ordering::Relaxed);
+        let handle: JoinHandle<Position> = thread::spawn(move || {
+            position.search(SearchLimit::infinite());
+            position
+        });
+        self.ponder = Some(Ponder { stop, handle });
+    }
+
+    /// 后台思考停止
+    ///
+    /// 停止后台思考并返回检索过的局面，没有后台思考时返回None。
+    fn stop_ponder(&mut self) -> Option<Position> {
+        let ponder: Ponder = self.ponder.take()?;
+        ponder.stop.store(true, Ordering::Relaxed);
+        ponder.handle.join().ok()
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server() -> Server {
+        let mut server = Server::new("resources/server_init.yaml");
+        server.config.ponder = true;
+        server.config.skill = None;
+        server.config.random_margin = None;
+        server
+    }
+
+    /// 没有检索结果的后台思考（ponderhit时不能直接使用结果）
+    fn pondering(server: &mut Server, fen: &str) {
+        let position = Position::from_fen(fen).unwrap();
+        let stop: Arc<AtomicBool> = position.stop_flag();
+        server.ponder = Some(Ponder { stop, handle: thread::spawn(move || position) });
+    }
+
+    /// 应答后局面的检索深度
+    fn searched_depth(next: Option<(Position, Vec<Move>)>) -> usize {
+        let (position, _) = next.unwrap();
+        position.multipv(1usize)[0].depth
+    }
+
+    #[test]
+    fn ponder_hit_without_reuse_searches_full_depth() {
+        let mut server = server();
+        let depth: usize = Position::new().default_limit().depth.unwrap();
+        pondering(&mut server, START_FEN);
+        let (response, next) = server.respond(START_FEN);
+        assert!(!response.is_empty());
+        assert!(server.ponder.is_none());
+        // 停止后台思考时设置的停止标志不影响重新检索
+        assert_eq!(searched_depth(next), depth);
+    }
+
+    #[test]
+    fn ponder_miss_searches_new_position() {
+        let mut server = server();
+        let fen: &str = "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C2C4/9/RNBAKABNR b";
+        let depth: usize = Position::from_fen(fen).unwrap().default_limit().depth.unwrap();
+        pondering(&mut server, START_FEN);
+        let (response, next) = server.respond(fen);
+        assert!(!response.is_empty());
+        assert!(server.ponder.is_none());
+        assert_eq!(searched_depth(next), depth);
+    }
+
+    #[test]
+    fn requests_share_transposition_table() {
+        let mut server = server();
+        let (_, next) = server.respond(START_FEN);
+        // 应答的局面使用服务器的置换表，不各自分配
+        assert!(next.is_some());
+        assert_eq!(Arc::strong_count(&server.table), 2usize);
+        drop(next);
+        assert_eq!(Arc::strong_count(&server.table), 1usize);
+    }
+}
@@ -0,0 +1,188 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/**************************************************************************************************/
+/**************************     TRANSPOSITION TABLE DEFINITION     *******************************/
+/**************************************************************************************************/
+// 置换表的默认大小（MB，每个记录16字节）
+pub const DEFAULT_HASH_SIZE: usize = 16;
+// 置换表大小的上限（MB）
+pub const MAX_HASH_SIZE: usize = 4096;
+// 1MB的记录数（2^16个）
+const ENTRIES_PER_MB_BITS: usize = 16;
+
+// 数据的位配置：评价值32位、深度8位、边界2位、着法（有无1位、开始7位、结束7位）、有效标志1位
+const DEPTH_SHIFT: u32 = 32;
+const BOUND_SHIFT: u32 = 40;
+const MOVE_SHIFT: u32 = 42;
+const FROM_SHIFT: u32 = 43;
+const TO_SHIFT: u32 = 50;
+const VALID_SHIFT: u32 = 57;
+
+/// 评价值的边界
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Bound {
+    /// 准确值。
+    Exact,
+    /// 下限（实际值大于等于该值）。
+    Lower,
+    /// 上限（实际值小于等于该值）。
+    Upper,
+}
+
+/// 置换表的记录
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TtEntry {
+    pub depth: usize,
+    /// 评价值（黑方视角，将死的步数为距该局面的步数）。
+    pub value: i32,
+    pub bound: Bound,
+    /// 最佳着法（开始位置, 结束位置）。
+    pub best: Option<(usize, usize)>,
+}
+
+impl TtEntry {
+    fn pack(&self) -> u64 {
+        let bound: u64 = match self.bound {
+            Bound::Exact => 0u64,
+            Bound::Lower => 1u64,
+            Bound::Upper => 2u64,
+        };
+        let best: u64 = match self.best {
+            Some((from, to)) => 1u64 << MOVE_SHIFT | (from as u64) << FROM_SHIFT | (to as u64) << TO_SHIFT,
+            None => 0u64,
+        };
+        // 有效标志使数据不为0（0为没有记录）
+        let valid: u64 = 1u64 << VALID_SHIFT;
+        self.value as u32 as u64 | (self.depth.min(255usize) as u64) << DEPTH_SHIFT | bound << BOUND_SHIFT | best | valid
+    }
+
+    fn unpack(data: u64) -> TtEntry {
+        TtEntry {
+            depth: (data >> DEPTH_SHIFT & 0xFF) as usize,
+            value: data as u32 as i32,
+            bound: match data >> BOUND_SHIFT & 0x3 {
+                0 => Bound::Exact,
+                1 => Bound::Lower,
+                _ => Bound::Upper,
+            },
+            best: if data >> MOVE_SHIFT & 0x1 == 1u64 {
+                Some(((data >> FROM_SHIFT & 0x7F) as usize, (data >> TO_SHIFT & 0x7F) as usize))
+            } else {
+                None
+            },
+        }
+    }
+}
+
+/// 置换表
+///
+/// 多个线程共享的无锁置换表。每个位置保存【键值 ^ 数据, 数据】，
+/// 读取时两者的异或与键值一致才有效，其它线程同时写入造成的不一致会被当作没有记录。
+pub struct TranspositionTable {
+    entries: Vec<[AtomicU64; 2]>,
+    mask: usize,
+}
+
+impl TranspositionTable {
+    /// 置换表新建
+    ///
+    /// * `bits` - 大小（2^bits个）。
+    pub fn new(bits: usize) -> TranspositionTable {
+        TranspositionTable {
+            entries: (0..1usize << bits).map(|_| [AtomicU64::new(0u64), AtomicU64::new(0u64)]).collect(),
+            mask: (1usize << bits) - 1usize,
+        }
+    }
+
+    /// 指定大小的置换表新建
+    ///
+    /// 记录数为2的幂，大小不是2的幂时向下取整。
+    ///
+    /// * `size` - 大小（MB，1~`MAX_HASH_SIZE`）。
+    pub fn with_size(size: usize) -> TranspositionTable {
+        let size: usize = size.clamp(1usize, MAX_HASH_SIZE);
+        TranspositionTable::new(ENTRIES_PER_MB_BITS + size.ilog2() as usize)
+    }
+
+    /// 大小（MB）
+    pub fn size(&self) -> usize {
+        self.entries.len() >> ENTRIES_PER_MB_BITS
+    }
+
+    /// 记录清除
+    ///
+    /// 清除所有记录（检索中不能调用）。
+    pub fn clear(&self) {
+        for slot in self.entries.iter() {
+            slot[0].store(0u64, Ordering::Relaxed);
+            slot[1].store(0u64, Ordering::Relaxed);
+        }
+    }
+
+    /// 记录读取
+    ///
+    /// * `key` - 局面键值（包含走棋方）。
+    pub fn probe(&self, key: u64) -> Option<TtEntry> {
+        let slot: &[AtomicU64; 2] = &self.entries[key as usize & self.mask];
+        let check: u64 = slot[0].load(Ordering::Relaxed);
+        let data: u64 = slot[1].load(Ordering::Relaxed);
+        if data != 0u64 && check ^ data == key {
+            Some(TtEntry::unpack(data))
+        } else {
+            None
+        }
+    }
+
+    /// 记录保存
+    ///
+    /// 同一局面已有更深的记录时不替换，其它局面的记录总是替换。
+    ///
+    /// * `key` - 局面键值（包含走棋方）。
+    /// * `entry` - 记录。
+    pub fn store(&self, key: u64, entry: TtEntry) {
+        if let Some(old) = self.probe(key) {
+            if old.depth > entry.depth {
+                return;
+            }
+        }
+        let slot: &[AtomicU64; 2] = &self.entries[key as usize & self.mask];
+        let data: u64 = entry.pack();
+        slot[0].store(key ^ data, Ordering::Relaxed);
+        slot[1].store(data, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entry_round_trips_through_table() {
+        let table = TranspositionTable::new(4usize);
+        let entries: [TtEntry; 3] = [
+            TtEntry { depth: 3usize, value: -899_990i32, bound: Bound::Upper, best: Some((89usize, 0usize)) },
+            TtEntry { depth: 0usize, value: 0i32, bound: Bound::Exact, best: None },
+            TtEntry { depth: 255usize, value: 123i32, bound: Bound::Lower, best: Some((40usize, 49usize)) },
+        ];
+        for (i, entry) in entries.iter().enumerate() {
+            let key: u64 = 0x1234_5678_9ABC_DEF0u64.wrapping_mul(i as u64 + 1u64);
+            table.store(key, *entry);
+            assert_eq!(table.probe(key), Some(*entry));
+            // 同一位置的其它局面读取不到
+            assert_eq!(table.probe(key ^ 0x100u64), None);
+        }
+    }
+
+    #[test]
+    fn table_size_and_clear() {
+        assert_eq!(TranspositionTable::with_size(DEFAULT_HASH_SIZE).size(), DEFAULT_HASH_SIZE);
+        // 2的幂以外向下取整，0为最小值
+        assert_eq!(TranspositionTable::with_size(3usize).size(), 2usize);
+        assert_eq!(TranspositionTable::with_size(0usize).size(), 1usize);
+        let table = TranspositionTable::with_size(1usize);
+        let entry = TtEntry { depth: 1usize, value: 10i32, bound: Bound::Exact, best: None };
+        table.store(42u64, entry);
+        table.clear();
+        assert_eq!(table.probe(42u64), None);
+    }
+}
@@ -0,0 +1,313 @@
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::BufReader;
+use std::sync::Arc;
+use std::time::Instant;
+
+use chess::*;
+
+/**************************************************************************************************/
+/*******************************     TUNE DEFINITION      *****************************************/
+/**************************************************************************************************/
+// 棋子种类（帅 士 相 马 车 炮 兵）
+const PIECE_KINDS: &str = "KABNRCP";
+// 棋子价值、位置分每次调整的幅度
+const MATERIAL_STEP: i32 = 5;
+const POSITION_STEP: i32 = 1;
+// 缩放系数K的搜索范围和精度
+const SCALE_MIN: f64 = 0.1;
+const SCALE_MAX: f64 = 3.0;
+const SCALE_PRECISION: f64 = 0.001;
+
+/// 训练局面
+///
+/// 静止局面以及对局结果（红方视角，胜1.0、和0.5、负0.0）。
+pub struct TunePosition {
+    pub position: Position,
+    pub result: f64,
+}
+
+/// 调整参数
+///
+/// 中局或残局的棋子价值（`square`为None）或者位置分表的一个位置（左右对称的位置一起调整）。
+#[derive(Debug, Clone, Copy)]
+struct Parameter {
+    endgame: bool,
+    kind: usize,
+    square: Option<usize>,
+}
+
+impl Parameter {
+    fn phase<'a>(&self, params: &'a mut EvaluateParams) -> &'a mut PhaseParams {
+        if self.endgame {
+            &mut params.endgame
+        } else {
+            &mut params.middlegame
+        }
+    }
+
+    /// 参数调整
+    ///
+    /// * `params` - 评价参数。
+    /// * `delta` - 调整幅度。
+    fn add(&self, params: &mut EvaluateParams, delta: i32) {
+        let phase: &mut PhaseParams = self.phase(params);
+        match self.square {
+            None => phase.material[self.kind] += delta,
+            Some(square) => {
+                let table: &mut Vec<i32> = phase.table_mut(self.kind);
+                let mirror: usize = square - square % WIDTH + WIDTH - 1usize - square % WIDTH;
+                table[square] += delta;
+                if mirror != square {
+                    table[mirror] += delta;
+                }
+            }
+        }
+    }
+
+    fn step(&self) -> i32 {
+        match self.square {
+            None => MATERIAL_STEP,
+            Some(_) => POSITION_STEP,
+        }
+    }
+}
+
+/// 训练局面读取
+///
+/// 每行为`<FEN> <结果>`，结果为红方视角的`1-0`、`0-1`或者`1/2-1/2`。
+/// 空行、注释行（#开头）以及走棋方被将军的局面（非静止局面）跳过。
+///
+/// * `file` - 训练局面文件。
+pub fn load(file: &str) -> Result<Vec<TunePosition>, String> {
+    let reader = File::open(file).map_err(|e| format!("unable to read [{}]: {}", file, e))?;
+    let mut positions: Vec<TunePosition> = Vec::new();
+    for (number, line) in BufReader::new(reader).lines().enumerate() {
+        let line: String = line.map_err(|e| format!("unable to read [{}]: {}", file, e))?;
+        let line: &str = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (fen, result) = match line.rfind(char::is_whitespace) {
+            Some(i) => (line[..i].trim(), &line[i + 1usize..]),
+            None => return Err(format!("missing result at line {}", number + 1usize)),
+        };
+        let result: f64 = match result {
+            "1-0" => 1.0f64,
+            "0-1" => 0.0f64,
+            "1/2-1/2" => 0.5f64,
+            _ => return Err(format!("invalid result [{}] at line {}", result, number + 1usize)),
+        };
+        let mut position: Position =
+            Position::from_fen(fen).map_err(|e| format!("{} at line {}", e, number + 1usize))?;
+        if !position.is_checked() {
+            positions.push(TunePosition { position, result });
+        }
+    }
+    Ok(positions)
+}
+
+/// 自动调整
+///
+/// 以Texel方法调整棋子价值（帅除外）和位置分表：评价值经过sigmoid转换为胜率，
+/// 按照与对局结果的均方误差，逐个参数加减调整幅度（局部搜索），误差变小时采用。
+/// 每一轮结束后写入评价参数文件，没有改善或者达到最大轮数时结束，返回最终的误差。
+///
+/// * `positions` - 训练局面。
+/// * `params` - 初始评价参数。
+/// * `iterations` - 最大轮数。
+/// * `output` - 输出的评价参数文件。
+pub fn run(
+    positions: &mut [TunePosition],
+    params: EvaluateParams,
+    iterations: usize,
+    output: &str,
+) -> Result<f64, String> {
+    if positions.is_empty() {
+        return Err(String::from("no positions to tune"));
+    }
+    let start: Instant = Instant::now();
+    let mut params: EvaluateParams = params;
+    let scale: f64 = find_scale(positions, &params);
+    let parameters: Vec<Parameter> = parameters(positions);
+    let mut best_error: f64 = error(positions, &params, scale);
+    println!(
+        "tune---positions=[{}],parameters=[{}],scale=[{:.3}],error=[{:.6}]",
+        positions.len(),
+        parameters.len(),
+        scale,
+        best_error
+    );
+
+    for iteration in 1usize..=iterations {
+        let mut improved = 0usize;
+        for parameter in parameters.iter() {
+            let step: i32 = parameter.step();
+            for delta in &[step, -step] {
+                let mut candidate: EvaluateParams = params.clone();
+                parameter.add(&mut candidate, *delta);
+                if candidate.validate().is_err() {
+                    continue;
+                }
+                let candidate_error: f64 = error(positions, &candidate, scale);
+                if candidate_error < best_error {
+                    params = candidate;
+                    best_error = candidate_error;
+                    improved += 1usize;
+                    break;
+                }
+            }
+        }
+        params.save(output)?;
+        println!(
+            "iteration---number=[{}],error=[{:.6}],improved=[{}],time=[{}ms]",
+            iteration,
+            best_error,
+            improved,
+            start.elapsed().as_millis()
+        );
+        if improved == 0usize {
+            break;
+        }
+    }
+    println!("output---file=[{}],error=[{:.6}]", output, best_error);
+    Ok(best_error)
+}
+
+/// 调整参数列表
+///
+/// 所有棋子价值（帅除外）以及训练局面中出现过的位置分（左右对称的位置只取左侧）。
+///
+/// * `positions` - 训练局面。
+fn parameters(positions: &[TunePosition]) -> Vec<Parameter> {
+    let mut occupied = [[false; MAX_CELLS_SIZE]; 7];
+    for tune in positions.iter() {
+        for square in 0usize..MAX_CELLS_SIZE {
+            if let Some(piece) = tune.position.piece_at(square) {
+                let kind: usize = PIECE_KINDS.find(piece.to_ascii_uppercase()).unwrap();
+                // 位置分表为红方视角，黑方使用旋转后的位置
+                let index: usize = if piece.is_ascii_uppercase() { square } else { MAX_CELLS_SIZE - 1usize - square };
+                let column: usize = index % WIDTH;
+                occupied[kind][index - column + column.min(WIDTH - 1usize - column)] = true;
+            }
+        }
+    }
+
+    let mut parameters: Vec<Parameter> = Vec::new();
+    for endgame in &[false, true] {
+        for kind in 1usize..7usize {
+            parameters.push(Parameter { endgame: *endgame, kind, square: None });
+        }
+        for (kind, squares) in occupied.iter().enumerate() {
+            for (square, _) in squares.iter().enumerate().filter(|(_, o)| **o) {
+                parameters.push(Parameter { endgame: *endgame, kind, square: Some(square) });
+            }
+        }
+    }
+    parameters
+}
+
+/// 误差计算
+///
+/// 所有训练局面的（结果 - sigmoid(评价值)）的平方的平均值。
+///
+/// * `positions` - 训练局面。
+/// * `params` - 评价参数。
+/// * `scale` - 缩放系数K。
+fn error(positions: &mut [TunePosition], params: &EvaluateParams, scale: f64) -> f64 {
+    let params: Arc<EvaluateParams> = Arc::new(params.clone());
+    let mut total = 0f64;
+    for tune in positions.iter_mut() {
+        tune.position.set_evaluate_params(params.clone());
+        // 评价值为黑方视角，转换为红方视角
+        let score: i32 = -tune.position.evaluate();
+        let difference: f64 = tune.result - sigmoid(score, scale);
+        total += difference * difference;
+    }
+    total / positions.len() as f64
+}
+
+/// 胜率转换
+///
+/// * `score` - 评价值（红方视角）。
+/// * `scale` - 缩放系数K。
+fn sigmoid(score: i32, scale: f64) -> f64 {
+    1f64 / (1f64 + 10f64.powf(-scale * score as f64 / 400f64))
+}
+
+/// 缩放系数K的计算
+///
+/// 以初始评价参数的误差最小为条件，三分搜索K。
+///
+/// * `positions` - 训练局面。
+/// * `params` - 评价参数。
+fn find_scale(positions: &mut [TunePosition], params: &EvaluateParams) -> f64 {
+    let mut low: f64 = SCALE_MIN;
+    let mut high: f64 = SCALE_MAX;
+    while high - low > SCALE_PRECISION {
+        let left: f64 = low + (high - low) / 3f64;
+        let right: f64 = high - (high - low) / 3f64;
+        if error(positions, params, left) < error(positions, params, right) {
+            high = right;
+        } else {
+            low = left;
+        }
+    }
+    (low + high) / 2f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+    use std::fs;
+
+    /// 合成的训练局面：多一个车的一方多数获胜，多一个马时多数和棋
+    fn synthetic() -> Vec<TunePosition> {
+        [
+            ("4k4/9/9/9/9/9/9/9/9/3K1R3 w", 1.0f64),
+            ("4k4/9/9/9/9/9/9/9/9/3K1R3 w", 1.0f64),
+            ("4k4/9/9/9/9/9/9/9/9/3K1R3 w", 0.5f64),
+            ("3k1r3/9/9/9/9/9/9/9/9/4K4 w", 0.0f64),
+            ("3k1r3/9/9/9/9/9/9/9/9/4K4 w", 0.0f64),
+            ("3k1r3/9/9/9/9/9/9/9/9/4K4 w", 0.5f64),
+            ("4k4/9/9/9/9/9/9/9/9/3K2N2 w", 0.5f64),
+            ("4k4/9/9/9/9/9/9/9/9/3K2N2 w", 0.5f64),
+            ("4k4/9/9/9/9/9/9/9/9/3K2N2 w", 1.0f64),
+        ]
+        .iter()
+        .map(|(fen, result)| TunePosition { position: Position::from_fen(fen).unwrap(), result: *result })
+        .collect()
+    }
+
+    #[test]
+    fn error_is_minimised_at_fitted_scale() {
+        let mut positions: Vec<TunePosition> = synthetic();
+        let params = EvaluateParams::default();
+        let scale: f64 = find_scale(&mut positions, &params);
+        assert!(scale > SCALE_MIN + 0.01f64 && scale < SCALE_MAX - 0.01f64, "{}", scale);
+        let best: f64 = error(&mut positions, &params, scale);
+        for k in &[SCALE_MIN, scale * 0.9f64, scale - 0.01f64, scale + 0.01f64, scale * 1.1f64, SCALE_MAX] {
+            assert!(error(&mut positions, &params, *k) >= best, "K={} scale={}", k, scale);
+        }
+    }
+
+    #[test]
+    fn tuning_decreases_error() {
+        let mut positions: Vec<TunePosition> = synthetic();
+        let params = EvaluateParams::default();
+        let scale: f64 = find_scale(&mut positions, &params);
+        let initial: f64 = error(&mut positions, &params, scale);
+
+        let output = env::temp_dir().join(format!("chess-tune-{}.yaml", std::process::id()));
+        let output: &str = output.to_str().unwrap();
+        let tuned: f64 = run(&mut positions, params.clone(), 1usize, output).unwrap();
+        assert!(tuned < initial, "{} >= {}", tuned, initial);
+        // 写出的参数有效，误差与返回值一致
+        let saved: EvaluateParams = EvaluateParams::load(output).unwrap();
+        fs::remove_file(output).unwrap();
+        assert_ne!(saved, params);
+        assert!((error(&mut positions, &saved, scale) - tuned).abs() < 1e-12f64);
+    }
+}
@@ -0,0 +1,340 @@
+use std::io;
+use std::io::prelude::*;
+use std::mem;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use chess::*;
+
+use cli::SearchOptions;
+
+/**************************************************************************************************/
+/*******************************     UCCI DEFINITION      *****************************************/
+/**************************************************************************************************/
+const ENGINE_NAME: &str = "chess";
+const ENGINE_AUTHOR: &str = "xiaanyin";
+// go time 指定的是剩余时间，每步使用其中的一部分
+const TIME_DIVISOR: u64 = 20;
+// go 没有指定限制时的深度
+const DEFAULT_DEPTH: usize = 4;
+// setoption multipv 的最大值
+const MAX_MULTIPV: usize = 32;
+// setoption randomness 的各级别对应的评价值范围
+const RANDOMNESS_MARGINS: [(&str, i32); 6] =
+    [("none", 0), ("tiny", 5), ("small", 10), ("medium", 20), ("large", 40), ("huge", 80)];
+
+/// UCCI协议
+///
+/// 从标准输入读取UCCI指令并应答，收到quit或输入结束时返回。
+/// 支持 ucci、isready、setoption（randomness/skill/threads/hash/multipv/pruning）、position、
+/// go（depth/nodes/movetime/time/infinite/ponder）、ponderhit、stop、quit。
+/// go 在其它线程检索，检索中也可以接收 stop、ponderhit 和 isready。
+/// go 在bestmove之前输出info（深度、评价值、节点数、ICCS格式的主要变例）以及中文纵线格式的主要变例，
+/// 将死时评价值为`mate N`。multipv大于1时按评价值从高到低输出各变例的info（`multipv 序号`）。
+/// 置换表在所有检索之间共用，setoption hash时重新分配。
+///
+/// * `search` - 检索设置的初始值（setoption可以修改）。
+pub fn run(search: SearchOptions) {
+    let stdin = io::stdin();
+    let mut search: SearchOptions = search;
+    let mut table: Arc<TranspositionTable> = Arc::new(TranspositionTable::with_size(search.hash));
+    // 置换表中是否有带噪声的评价值（棋力级别）
+    let mut noisy: bool = false;
+    let mut position = Position::new();
+    let mut randomness = Randomness {
+        margin: 0i32,
+        seed: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0u64),
+    };
+    let mut skill: usize = MAX_SKILL_LEVEL;
+    let mut multipv: usize = 1usize;
+    let mut searching: Option<Searching> = None;
+
+    for line in stdin.lock().lines() {
+        let line: String = match line {
+            Ok(l) => l,
+            Err(_) => break,
+        };
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("ucci") => {
+                println!("id name {}", ENGINE_NAME);
+                println!("id author {}", ENGINE_AUTHOR);
+                println!("option randomness type combo var none var tiny var small var medium var large var huge default none");
+                println!("option skill type spin min 0 max {} default {}", MAX_SKILL_LEVEL, MAX_SKILL_LEVEL);
+                println!("option threads type spin min 1 max {} default {}", MAX_SEARCH_THREADS, search.threads);
+                println!("option hash type spin min 1 max {} default {}", MAX_HASH_SIZE, table.size());
+                println!("option multipv type spin min 1 max {} default 1", MAX_MULTIPV);
+                println!("option pruning type string default all");
+                println!("ucciok");
+            }
+            Some("isready") => println!("readyok"),
+            Some("setoption") => {
+                wait(&mut searching, &mut position, false);
+                match tokens.collect::<Vec<&str>>().as_slice() {
+                    ["randomness", level] => match RANDOMNESS_MARGINS.iter().find(|(name, _)| name == level) {
+                        Some((_, margin)) => randomness.margin = *margin,
+                        None => println!("info string invalid randomness [{}]", level),
+                    },
+                    ["skill", level] => match level.parse::<usize>() {
+                        Ok(level) if level <= MAX_SKILL_LEVEL => skill = level,
+                        _ => println!("info string invalid skill [{}]", level),
+                    },
+                    ["threads", threads] => match threads.parse::<usize>() {
+                        Ok(threads) if (1usize..=MAX_SEARCH_THREADS).contains(&threads) => search.threads = threads,
+                        _ => println!("info string invalid threads [{}]", threads),
+                    },
+                    ["hash", size] => match size.parse::<usize>() {
+                        Ok(size) if (1usize..=MAX_HASH_SIZE).contains(&size) => {
+                            search.hash = size;
+                            table = Arc::new(TranspositionTable::with_size(size));
+                        }
+                        _ => println!("info string invalid hash [{}]", size),
+                    },
+                    ["pruning", text] => match Selectivity::parse(text) {
+                        Ok(selectivity) => search.selectivity = selectivity,
+                        Err(message) => println!("info string {}", message),
+                    },
+                    ["multipv", count] => match count.parse::<usize>() {
+                        Ok(count) if (1usize..=MAX_MULTIPV).contains(&count) => multipv = count,
+                        _ => println!("info string invalid multipv [{}]", count),
+                    },
+                    _ => {}
+                }
+            }
+            Some("position") => {
+                wait(&mut searching, &mut position, false);
+                if let Err(message) = set_position(&mut position, &tokens.collect::<Vec<&str>>()) {
+                    println!("info string {}", message);
+                }
+            }
+            Some("go") => {
+                wait(&mut searching, &mut position, false);
+                let tokens: Vec<&str> = tokens.collect();
+                let limit: SearchLimit = parse_go(&tokens);
+                // 每次检索使用不同的种子（棋力级别会覆盖着法随机化，所以先设置）
+                search.apply(&mut position);
+                // 棋力级别的评价噪声每次检索不同，使用前后清除共用的置换表
+                if skill < MAX_SKILL_LEVEL || noisy {
+                    table.clear();
+                }
+                noisy = skill < MAX_SKILL_LEVEL;
+                position.set_transposition_table(table.clone());
+                position.set_skill(Skill { level: skill, seed: randomness.seed });
+                if randomness.margin > 0i32 || skill >= MAX_SKILL_LEVEL {
+                    position.set_randomness(randomness);
+                }
+                randomness.seed = randomness.seed.wrapping_add(1u64);
+                // 后台思考在ponderhit或者stop之前无限检索，ponderhit后以原来的限制检索
+                searching = Some(if tokens.contains(&"ponder") {
+                    start(&mut position, SearchLimit::infinite(), multipv, Some(limit))
+                } else {
+                    start(&mut position, limit, multipv, None)
+                });
+            }
+            Some("ponderhit") => {
+                if let Some(limit) = wait(&mut searching, &mut position, true) {
+                    searching = Some(start(&mut position, limit, multipv, None));
+                }
+            }
+            Some("stop") => {
+                // 后台思考被停止时输出到此为止的结果
+                let pondering: bool = wait(&mut searching, &mut position, true).is_some();
+                if pondering {
+                    let result: Option<SearchResult> = position.multipv(1usize).into_iter().next();
+                    report(&mut position, result, multipv);
+                }
+            }
+            Some("quit") => {
+                wait(&mut searching, &mut position, false);
+                println!("bye");
+                break;
+            }
+            _ => {}
+        }
+        io::stdout().flush().unwrap();
+    }
+    wait(&mut searching, &mut position, false);
+}
+
+/// 检索中的线程
+struct Searching {
+    stop: Arc<AtomicBool>,
+    infinite: bool,
+    /// 后台思考时为ponderhit后的检索限制。
+    ponder: Option<SearchLimit>,
+    handle: JoinHandle<Position>,
+}
+
+/// 检索开始
+///
+/// 在其它线程检索，结束后归还局面。后台思考时不输出结果。
+///
+/// * `position` - 局面（检索中替换为初始局面）。
+/// * `limit` - 检索限制。
+/// * `multipv` - 变例数。
+/// * `ponder` - 后台思考时为ponderhit后的检索限制。
+fn start(position: &mut Position, limit: SearchLimit, multipv: usize, ponder: Option<SearchLimit>) -> Searching {
+    let stop: Arc<AtomicBool> = position.stop_flag();
+    stop.store(false, Ordering::Relaxed);
+    let mut searching_position: Position = mem::replace(position, Position::new());
+    let flag: Arc<AtomicBool> = stop.clone();
+    let output: bool = ponder.is_none();
+    let handle = thread::spawn(move || {
+        go(&mut searching_position, limit, multipv, &flag, output);
+        searching_position
+    });
+    Searching { stop, infinite: limit.infinite, ponder, handle }
+}
+
+/// 检索结束等待
+///
+/// 检索中时等待结束并取回局面。`stop`为true或者无限检索时先设置停止标志。
+/// 结束的是后台思考时返回ponderhit后的检索限制。
+///
+/// * `searching` - 检索中的线程。
+/// * `position` - 取回的局面。
+/// * `stop` - 是否停止检索。
+fn wait(searching: &mut Option<Searching>, position: &mut Position, stop: bool) -> Option<SearchLimit> {
+    let search: Searching = searching.take()?;
+    if stop || search.infinite {
+        search.stop.store(true, Ordering::Relaxed);
+    }
+    if let Ok(p) = search.handle.join() {
+        *position = p;
+    }
+    search.ponder
+}
+
+/// 检索
+///
+/// 检索后输出结果。无限检索在停止标志被设置之前不输出bestmove。
+///
+/// * `position` - 局面。
+/// * `limit` - 检索限制。
+/// * `multipv` - 变例数。
+/// * `stop` - 停止标志。
+/// * `output` - 是否输出结果。
+fn go(position: &mut Position, limit: SearchLimit, multipv: usize, stop: &AtomicBool, output: bool) {
+    // 多变例需要所有根节点着法的准确值
+    let result: Option<SearchResult> =
+        if multipv > 1usize { position.search_exact(limit) } else { position.search(limit) };
+    if limit.infinite {
+        while !stop.load(Ordering::Relaxed) {
+            thread::sleep(Duration::from_millis(1u64));
+        }
+    }
+    if output {
+        report(position, result, multipv);
+    }
+}
+
+/// 结果输出
+///
+/// 输出渴望窗口的失败（`lowerbound`/`upperbound`）、info和bestmove，主要变例有第二步时以`ponder`附加预想的对方着法。
+///
+/// * `position` - 局面。
+/// * `result` - 检索结果。
+/// * `multipv` - 变例数。
+fn report(position: &mut Position, result: Option<SearchResult>, multipv: usize) {
+    match result {
+        Some(result) => {
+            // 渴望窗口的失败：fail high的评价值为下限，fail low为上限
+            for fail in position.aspiration_fails() {
+                println!(
+                    "info depth {} score {} {}",
+                    fail.depth,
+                    fail.score,
+                    if fail.high { "lowerbound" } else { "upperbound" }
+                );
+            }
+            if multipv > 1usize {
+                for (i, line) in position.multipv(multipv).iter().enumerate() {
+                    println!(
+                        "info depth {} multipv {} score {} nodes {} pv {}",
+                        line.depth,
+                        i + 1usize,
+                        line.score_text(),
+                        line.nodes,
+                        line.pv_iccs()
+                    );
+                    println!("info string multipv {} pv {}", i + 1usize, position.line_to_chinese(&line.pv).join(" "));
+                }
+            } else {
+                println!(
+                    "info depth {} score {} nodes {} pv {}",
+                    result.depth,
+                    result.score_text(),
+                    result.nodes,
+                    result.pv_iccs()
+                );
+                println!("info string pv {}", position.line_to_chinese(&result.pv).join(" "));
+            }
+            match result.pv.get(1usize) {
+                Some(reply) => println!("bestmove {} ponder {}", result.best_move.to_iccs(), reply.to_iccs()),
+                None => println!("bestmove {}", result.best_move.to_iccs()),
+            }
+        }
+        None => println!("nobestmove"),
+    }
+    io::stdout().flush().unwrap();
+}
+
+/// 局面设置
+///
+/// 解析`position {fen <FEN> | startpos} [moves <着法>...]`，设置局面并执行后续着法。
+/// 格式错误或者着法不合法时返回Err，局面保持不变。
+///
+/// * `position` - 局面。
+/// * `tokens` - position之后的参数。
+fn set_position(position: &mut Position, tokens: &[&str]) -> Result<(), String> {
+    let moves_index: usize = tokens
+        .iter()
+        .position(|t| *t == "moves")
+        .unwrap_or(tokens.len());
+    let mut new_position: Position = match tokens.first() {
+        Some(&"startpos") => Position::new(),
+        Some(&"fen") if moves_index > 1usize => Position::from_fen(&tokens[1..moves_index].join(" "))?,
+        _ => return Err(String::from("invalid position")),
+    };
+
+    for iccs in tokens.iter().skip(moves_index + 1usize) {
+        let m: Move = new_position.parse_move(iccs)?;
+        new_position.make_move(m)?;
+    }
+    *position = new_position;
+    Ok(())
+}
+
+/// 检索限制解析
+///
+/// 解析`go [depth <深度>] [nodes <节点数>] [movetime <毫秒>] [time <剩余时间(毫秒)>] [infinite]`，
+/// 可以组合使用。time使用剩余时间的1/`TIME_DIVISOR`（指定movetime时忽略），都没有指定时为默认深度。
+///
+/// * `tokens` - go之后的参数。
+fn parse_go(tokens: &[&str]) -> SearchLimit {
+    let value = |name: &str| -> Option<u64> {
+        tokens
+            .iter()
+            .position(|t| *t == name)
+            .and_then(|i| tokens.get(i + 1usize))
+            .and_then(|v| v.parse().ok())
+    };
+    let limit = SearchLimit {
+        depth: value("depth").map(|depth| depth as usize),
+        nodes: value("nodes"),
+        time: value("movetime")
+            .or_else(|| value("time").map(|time| time / TIME_DIVISOR))
+            .map(Duration::from_millis),
+        infinite: tokens.contains(&"infinite"),
+    };
+    if limit == SearchLimit::default() {
+        SearchLimit::depth(DEFAULT_DEPTH)
+    } else {
+        limit
+    }
+}